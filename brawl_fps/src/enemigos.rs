@@ -0,0 +1,193 @@
+use macroquad::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+use crate::nivel::{EnemyDef, LevelDef, LevelState};
+use crate::raycaster::GridWalk;
+
+// Enemigos (los guardias de Lord Farquaad): recorren su ronda de celdas y, cuando ven al
+// jugador, lo persiguen. Ver al jugador es tirar un rayo DDA por la cuadrícula, igual que el
+// raycaster; para rodear paredes buscan el camino con BFS sobre el mapa. No hay azar: con la
+// misma entrada una partida se repite igual.
+
+/// Distancia a la que un enemigo alcanza al jugador.
+const CONTACT_RADIUS: f32 = 0.45;
+/// Segundos que sigue buscando donde vio al jugador por última vez antes de volver a su ronda.
+pub const GIVE_UP_SECS: f32 = 3.0;
+/// Cuánto más rápido va persiguiendo que de ronda.
+const CHASE_SPEEDUP: f32 = 1.3;
+/// Distancia a la que da por alcanzado un punto del camino.
+const ARRIVE_DIST: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnemyMode {
+    Patrol,
+    /// Vio al jugador: va hacia él, o hacia donde lo vio por última vez.
+    Chase,
+}
+
+/// Estado de un enemigo en la partida.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enemy {
+    /// Índice en `LevelDef::enemies`.
+    pub def: usize,
+    pub pos: Vec2,
+    /// Hacia dónde mira (normalizado).
+    pub dir: Vec2,
+    /// Si se movió en el último paso (para la animación de caminar).
+    pub moving: bool,
+    pub mode: EnemyMode,
+    /// Punto de la ronda al que se dirige.
+    waypoint: usize,
+    /// Celdas que faltan hasta `path_goal`, la próxima al final.
+    path: Vec<(i32, i32)>,
+    path_goal: Option<(i32, i32)>,
+    last_seen: Vec2,
+    unseen_for: f32,
+}
+
+impl Enemy {
+    pub fn new(index: usize, def: &EnemyDef) -> Self {
+        let pos = cell_center(def.route[0]);
+        let next = cell_center(def.route[1 % def.route.len()]);
+        let dir = (next - pos).try_normalize().unwrap_or(vec2(0.0, 1.0));
+        Self {
+            def: index,
+            pos,
+            dir,
+            moving: false,
+            mode: EnemyMode::Patrol,
+            waypoint: 1 % def.route.len(),
+            path: Vec::new(),
+            path_goal: None,
+            last_seen: pos,
+            unseen_for: 0.0,
+        }
+    }
+
+    /// Decide a dónde ir y avanza `dt` segundos.
+    fn think(&mut self, state: &LevelState, def: &EnemyDef, player: Vec2, dt: f32) {
+        let sees = self.pos.distance(player) <= def.vision && line_of_sight(state, self.pos, player);
+        if sees {
+            self.mode = EnemyMode::Chase;
+            self.last_seen = player;
+            self.unseen_for = 0.0;
+        } else if self.mode == EnemyMode::Chase {
+            self.unseen_for += dt;
+            if self.unseen_for >= GIVE_UP_SECS {
+                self.mode = EnemyMode::Patrol;
+            }
+        }
+
+        let (goal, speed) = match self.mode {
+            EnemyMode::Chase => (self.last_seen, def.speed * CHASE_SPEEDUP),
+            EnemyMode::Patrol => (cell_center(def.route[self.waypoint]), def.speed),
+        };
+
+        // a la vista: en línea recta; si no, celda por celda por el camino más corto
+        let next = if sees {
+            self.path_goal = None;
+            Some(goal)
+        } else {
+            // recalcula si cambió el destino o si una puerta se cerró en el camino
+            let goal_cell = cell_of(goal);
+            let blocked = self.path.last().is_some_and(|&(x, y)| state.blocks_at(x, y));
+            if self.path_goal != Some(goal_cell) || blocked {
+                let path = find_path(state, cell_of(self.pos), goal_cell);
+                // sin camino: se queda quieto y lo vuelve a intentar en el próximo paso
+                self.path_goal = path.is_some().then_some(goal_cell);
+                self.path = path.unwrap_or_default();
+            }
+            while let Some(&cell) = self.path.last()
+                && self.pos.distance(cell_center(cell)) < ARRIVE_DIST
+            {
+                self.path.pop();
+            }
+            self.path_goal.map(|_| self.path.last().map_or(goal, |&cell| cell_center(cell)))
+        };
+
+        let to = next.unwrap_or(self.pos) - self.pos;
+        let dist = to.length();
+        self.moving = dist > ARRIVE_DIST;
+        if self.moving {
+            self.dir = to / dist;
+            self.pos += self.dir * (speed * dt).min(dist);
+        } else if self.mode == EnemyMode::Patrol {
+            // llegó al punto de la ronda (o no hay cómo llegar): sigue con el próximo
+            self.waypoint = (self.waypoint + 1) % def.route.len();
+        }
+    }
+}
+
+/// Mueve a todos los enemigos `dt` segundos: de ronda, o detrás del jugador en `player` si lo
+/// ven. El que lo alcanza le quita vida (si el jugador no sigue invulnerable por otro golpe);
+/// devuelve el índice (en `LevelDef::enemies`) del que golpeó, si alguno, para el sonido.
+pub fn update_enemies(state: &mut LevelState, def: &LevelDef, player: Vec2, dt: f32) -> Option<usize> {
+    let mut enemies = std::mem::take(&mut state.enemies);
+    let mut hit = None;
+    for enemy in &mut enemies {
+        let Some(ed) = def.enemies.get(enemy.def) else { continue };
+        enemy.think(state, ed, player, dt);
+        if enemy.pos.distance(player) < CONTACT_RADIUS && state.hurt(ed.damage) {
+            hit = Some(enemy.def);
+        }
+    }
+    state.enemies = enemies;
+    hit
+}
+
+/// Si desde `from` se ve `to`: ninguna celda bloqueante (paredes, puertas cerradas, la salida
+/// cerrada) en el segmento entre los dos.
+pub fn line_of_sight(state: &LevelState, from: Vec2, to: Vec2) -> bool {
+    let target = cell_of(to);
+    if cell_of(from) == target {
+        return true;
+    }
+    for step in GridWalk::new(from, to - from) {
+        // con `to - from` como dirección, el segmento termina en distancia 1
+        if (step.x, step.y) == target || step.enter > 1.0 {
+            return true;
+        }
+        if state.blocks_at(step.x, step.y) {
+            return false;
+        }
+    }
+    unreachable!("GridWalk no termina")
+}
+
+/// Camino más corto (BFS, 4 vecinos) de `from` a `to` por celdas transitables. Devuelve las
+/// celdas a recorrer sin incluir `from`, con la próxima al final; `None` si no hay camino.
+pub fn find_path(state: &LevelState, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    came_from.insert(from, from);
+    while let Some(cell) = queue.pop_front() {
+        if cell == to {
+            let mut path = vec![cell];
+            let mut c = cell;
+            while came_from[&c] != from {
+                c = came_from[&c];
+                path.push(c);
+            }
+            if cell == from {
+                path.clear();
+            }
+            return Some(path);
+        }
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = (cell.0 + dx, cell.1 + dy);
+            if !came_from.contains_key(&next) && !state.blocks_at(next.0, next.1) {
+                came_from.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+fn cell_of(p: Vec2) -> (i32, i32) {
+    (p.x.floor() as i32, p.y.floor() as i32)
+}
+
+fn cell_center((x, y): (i32, i32)) -> Vec2 {
+    vec2(x as f32 + 0.5, y as f32 + 0.5)
+}
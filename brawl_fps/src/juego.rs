@@ -0,0 +1,684 @@
+use macroquad::prelude::*;
+use macroquad::texture::FilterMode;
+use macroquad::audio::{load_sound, play_sound, stop_sound, PlaySoundParams, Sound};
+use std::collections::HashMap;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::nivel::{DoorEvent, DoorLock, ItemEffect, LevelDef, LevelState, PickupKind, CELL_EXIT, CELL_WALL, INVULNERABLE_SECS};
+use crate::puntaje::{format_time, RunResults};
+use crate::raycaster::{render_frame, Billboard, Camera, Framebuffer, SceneAssets, TextureData};
+use crate::records::{ghost_path, records_path, RecordTable, MAX_INITIALS};
+use crate::repeticion::{Ghost, Recording};
+use crate::simulacion::{update, Game, GameEvent, InputFrame, TICK_DT};
+
+// ====== Config ======
+const MINIMAP_SCALE: f32 = 6.0; // px por celda en minimapa
+const MAX_FPS: Option<f32> = Some(60.0); // tope de cuadros del raycaster por segundo (None = sin tope)
+const MAX_TICKS_PER_FRAME: u32 = 8; // pasos de simulación como mucho por cuadro
+const MOUSE_SENSITIVITY: f32 = 0.003; // sensibilidad reducida
+const RENDER_SCALE: f32 = 0.5; // resolución interna del raycaster respecto a la ventana
+const MESSAGE_SECS: f64 = 2.0; // duración de los avisos en pantalla (puerta cerrada, ...)
+const DAMAGE_FLASH_SECS: f32 = 0.3; // duración del destello rojo al recibir daño
+const BUTTON_STEP: f32 = 64.0; // distancia vertical entre botones apilados de las pantallas superpuestas
+const GHOST_TRAIL_STEP: usize = 6; // pasos entre puntos del rastro del fantasma en el minimapa
+
+/// Retorna si una celda es pared (solo tipo 1)
+fn is_wall(cell: i32) -> bool {
+    cell == CELL_WALL
+}
+
+/// Color del placeholder para las texturas conocidas (magenta para el resto).
+fn placeholder_color(name: &str) -> (u8, u8, u8) {
+    match name {
+        "planicie" => (10, 200, 100),
+        "bosque" => (30, 120, 30),
+        "castillo" => (160, 160, 200),
+        "burro" => (240, 200, 50),
+        "fiona" => (200, 80, 120),
+        "gato" => (150, 100, 250),
+        "puerta" => (120, 80, 45),
+        "moneda" => (250, 210, 40),
+        "llave" => (200, 200, 210),
+        "cebolla" => (200, 150, 220),
+        "pocion" => (80, 200, 240),
+        _ => (255, 0, 255),
+    }
+}
+
+/// Carga (en memoria, para el raycaster) todas las texturas declaradas por el nivel;
+/// las que fallan se sustituyen por un placeholder.
+async fn load_level_textures(def: &LevelDef) -> HashMap<String, TextureData> {
+    let mut textures = HashMap::new();
+    for (name, path) in &def.textures {
+        let tex = match load_image(path).await {
+            Ok(img) => TextureData::from_image(&img),
+            Err(e) => {
+                eprintln!("Warning: no se pudo cargar {}: {}. Usando placeholder.", path, e);
+                let (r, g, b) = placeholder_color(name);
+                TextureData::solid(r, g, b)
+            }
+        };
+        textures.insert(name.clone(), tex);
+    }
+    textures
+}
+
+/// Carga un sonido opcional del nivel; `None` si no está declarado o no se puede leer.
+async fn level_sound(path: Option<&String>, what: &str) -> Option<Sound> {
+    let path = path?;
+    match load_sound(path).await {
+        Ok(s) => Some(s),
+        Err(e) => {
+            eprintln!("Warning: no se pudo cargar {}: {}. {} deshabilitado.", path, e, what);
+            None
+        }
+    }
+}
+
+/// Mejor partida guardada del nivel (la que se usa como fantasma), si hay una y es de este nivel.
+fn load_best_run(def: &LevelDef) -> Option<Recording> {
+    let path = ghost_path(&def.name).filter(|p| p.exists())?;
+    match Recording::load(&path.to_string_lossy()) {
+        Ok(rec) if rec.level == def.name => Some(rec),
+        Ok(rec) => {
+            eprintln!("Warning: {} es del nivel `{}`. Fantasma deshabilitado.", path.display(), rec.level);
+            None
+        }
+        Err(e) => {
+            eprintln!("Warning: no se pudo cargar el fantasma: {}. Fantasma deshabilitado.", e);
+            None
+        }
+    }
+}
+
+/// Pedido de volver al menú escrito en la terminal. Es global porque el hilo lector de stdin
+/// sobrevive a la partida: se lanza una sola vez y sirve a todas las entradas a niveles.
+static RETURN_TO_MENU: AtomicBool = AtomicBool::new(false);
+static STDIN_READER: Once = Once::new();
+
+/// Lanza (una única vez) el hilo que espera `menu` / `m` / `return` en stdin.
+fn spawn_stdin_reader() {
+    STDIN_READER.call_once(|| {
+        std::thread::spawn(|| {
+            // hilo bloqueante que espera líneas de stdin
+            let stdin = std::io::stdin();
+            loop {
+                let mut buf = String::new();
+                match stdin.read_line(&mut buf) {
+                    // EOF o error: terminamos el hilo
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        let cmd = buf.trim().to_lowercase();
+                        if cmd == "menu" || cmd == "m" || cmd == "return" {
+                            RETURN_TO_MENU.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+    });
+}
+
+/// Ejecuta un nivel hasta que el jugador vuelve al menú. Con `replay`, la partida la juega la
+/// grabación en lugar del teclado y el mouse; con `ghost_mode`, la mejor partida anterior corre
+/// al lado como un fantasma. Devuelve la entrada de la última partida (desde el último
+/// reintento), para guardarla como repetición.
+pub async fn run_level(def: &LevelDef, replay: Option<&Recording>, ghost_mode: bool) -> Recording {
+    // estado de esta partida: se clona de la plantilla para que repetir el nivel empiece de cero
+    let mut game = Game::new(def);
+    let mut recording = Recording::new(&def.name);
+
+    // ---- Fantasma ----
+    // la partida ganada más rápida se guarda siempre; solo se muestra con el modo fantasma. Una
+    // guardada que ya no gana el nivel (el mapa cambió, archivo cortado) no cuenta como la mejor
+    let best_run = load_best_run(def).map(|r| Ghost::new(def, &r)).filter(|g| g.won);
+    let mut best_ticks = best_run.as_ref().map(|g| g.path.len() - 1);
+    let ghost = best_run.filter(|_| ghost_mode);
+    let mut ghost_saved = false;
+
+    // ---- Texturas ----
+    let textures = load_level_textures(def).await;
+
+    let assets = SceneAssets::from_level(def, &textures);
+
+    // ---- Audios ----
+    // Guardamos las Sound en Option<Sound> (no las movemos fuera; usaremos as_ref() para pasar &Sound).
+    let bg_sound_opt: Option<Sound> = level_sound(def.music.as_ref(), "Audio de fondo").await;
+    let final_sound_opt: Option<Sound> = level_sound(def.sounds.get("final"), "Sonido final").await;
+    let mut pickup_sounds: HashMap<PickupKind, Sound> = HashMap::new();
+    let kinds = def.collectibles.iter().map(|c| PickupKind::Friend(c.cell)).chain(def.items.iter().map(|i| PickupKind::Item(i.id)));
+    for kind in kinds {
+        let Some(name) = def.pickup_sound(kind) else { continue };
+        if let Some(s) = level_sound(def.sounds.get(name), "Sonido al recoger").await {
+            pickup_sounds.insert(kind, s);
+        }
+    }
+    let mut enemy_sounds: Vec<Option<Sound>> = Vec::new();
+    for e in &def.enemies {
+        enemy_sounds.push(level_sound(e.sound.as_ref().and_then(|n| def.sounds.get(n)), "Sonido de golpe").await);
+    }
+    let mut hazard_sounds: HashMap<i32, Sound> = HashMap::new();
+    for h in &def.hazards {
+        if let Some(s) = level_sound(h.sound.as_ref().and_then(|n| def.sounds.get(n)), "Sonido de peligro").await {
+            hazard_sounds.insert(h.id, s);
+        }
+    }
+
+    // Flags para controlar reproducción/pausa/estado final
+    let mut bg_playing = false;
+    let mut bg_should_play = true; // cuando final suena lo ponemos en false para evitar reinicios
+    let mut final_played = false;
+
+    // Iniciamos el audio de fondo en bucle (si está disponible)
+    if let Some(bg_ref) = bg_sound_opt.as_ref() {
+        play_sound(bg_ref, PlaySoundParams { looped: true, volume: 0.6 });
+        bg_playing = true;
+    }
+
+    // framebuffer del raycaster; se sube a una textura y se escala a la ventana
+    let mut fb = Framebuffer::new(1, 1);
+    let mut screen_tex = Texture2D::from_rgba8(1, 1, &fb.to_rgba8());
+
+    // tiempo real todavía no simulado, y entrada que espera al próximo paso
+    let mut accumulator = 0.0;
+    let mut pending_turn = 0.0;
+    let mut pending_use = false;
+    // cuándo se dibujó el último cuadro del raycaster (para el tope de FPS)
+    let mut last_render = f64::NEG_INFINITY;
+
+    let mut mouse_look = true;
+    set_cursor_grab(true);
+    show_mouse(false);
+    let mut last_mouse_x = mouse_position().0;
+
+    let mut paused = false;
+    // aviso temporal en pantalla y hasta cuándo mostrarlo
+    let mut message: Option<(String, f64)> = None;
+    // puntaje de la partida, calculado una vez al ganar
+    let mut results: Option<RunResults> = None;
+    // iniciales que se están escribiendo si la partida entra en la tabla de récords
+    let mut initials: Option<String> = None;
+    let mut record_saved = false;
+
+    // --- orden desde terminal ---
+    // descartamos pedidos viejos (p. ej. escritos mientras estábamos en el menú)
+    RETURN_TO_MENU.store(false, Ordering::Relaxed);
+    if def.terminal_commands {
+        spawn_stdin_reader();
+    }
+
+    loop {
+        let dt = get_frame_time();
+        clear_background(BLACK);
+
+        // ====== INPUT ======
+        if is_key_pressed(KeyCode::Escape) {
+            paused = !paused;
+
+            // Pausamos/Despausamos audio de fondo (simulación: stop/replay)
+            if paused {
+                // Pausamos: paramos el sonido de fondo si está sonando
+                if bg_playing {
+                    if let Some(bg_ref) = bg_sound_opt.as_ref() {
+                        stop_sound(bg_ref);
+                    }
+                    bg_playing = false;
+                }
+                set_cursor_grab(false);
+                show_mouse(true);
+            } else {
+                // Despausamos: volvemos a iniciar el fondo si corresponde y no hemos llegado al final
+                if bg_should_play && !bg_playing && !final_played
+                    && let Some(bg_ref) = bg_sound_opt.as_ref()
+                {
+                    play_sound(bg_ref, PlaySoundParams { looped: true, volume: 0.6 });
+                    bg_playing = true;
+                }
+                if mouse_look {
+                    set_cursor_grab(true);
+                    show_mouse(false);
+                }
+                last_mouse_x = mouse_position().0;
+            }
+        }
+
+        if is_key_pressed(KeyCode::M) {
+            mouse_look = !mouse_look;
+            if mouse_look && !paused {
+                set_cursor_grab(true);
+                show_mouse(false);
+                last_mouse_x = mouse_position().0;
+            } else {
+                set_cursor_grab(false);
+                show_mouse(true);
+            }
+        }
+
+        // ====== SIMULACIÓN A PASO FIJO ======
+        // (si no pausado ni terminada la partida) el tiempo real se acumula y se consume en pasos
+        // de TICK_DT; el giro del mouse y la tecla de usar se guardan hasta que un paso los use
+        if !paused && !game.finished() {
+            if mouse_look {
+                let (mx, _) = mouse_position();
+                pending_turn += (mx - last_mouse_x) * MOUSE_SENSITIVITY;
+                last_mouse_x = mx;
+            }
+            // E: abrir/cerrar la puerta de enfrente
+            pending_use |= is_key_pressed(KeyCode::E);
+
+            // tras un tirón (carga, ventana arrastrada) se descarta el tiempo que no entra
+            accumulator = (accumulator + dt).min(TICK_DT * MAX_TICKS_PER_FRAME as f32);
+            while accumulator >= TICK_DT {
+                accumulator -= TICK_DT;
+                let input = match replay {
+                    // se acabó la grabación: el jugador se queda quieto
+                    Some(rec) => rec.inputs.get(recording.inputs.len()).copied().unwrap_or_default(),
+                    None => InputFrame { turn: pending_turn, use_door: pending_use, ..held_input() },
+                };
+                pending_turn = 0.0;
+                pending_use = false;
+                recording.inputs.push(input);
+
+                for event in update(&mut game, &input, TICK_DT) {
+                    match event {
+                        // Reproducir sonido de lo recogido sin detener el fondo (si está disponible)
+                        GameEvent::Picked(kind) => {
+                            if let Some(sound) = pickup_sounds.get(&kind) {
+                                play_sound(sound, PlaySoundParams { looped: false, volume: 0.95 });
+                            }
+                        }
+                        GameEvent::EnemyHit(i) => {
+                            if let Some(Some(sound)) = enemy_sounds.get(i) {
+                                play_sound(sound, PlaySoundParams { looped: false, volume: 1.0 });
+                            }
+                        }
+                        GameEvent::HazardHit(id) => {
+                            if let Some(sound) = hazard_sounds.get(&id) {
+                                play_sound(sound, PlaySoundParams { looped: false, volume: 1.0 });
+                            }
+                        }
+                        GameEvent::DoorLocked(lock) => {
+                            let text = match lock {
+                                DoorLock::Key(key) => format!("Necesitas la llave {}", key),
+                                DoorLock::Event(DoorEvent::FriendsFound) => "Se abrirá cuando encuentres a todos tus amigos".to_string(),
+                            };
+                            message = Some((text, get_time() + MESSAGE_SECS));
+                        }
+                        GameEvent::Won => {
+                            let res = RunResults::new(def, &game.level);
+                            // la partida más rápida pasa a ser el fantasma del nivel (las repeticiones no cuentan)
+                            if replay.is_none()
+                                && best_ticks.is_none_or(|n| recording.inputs.len() < n)
+                                && let Some(path) = ghost_path(&def.name)
+                            {
+                                match recording.save(&path) {
+                                    Ok(()) => {
+                                        best_ticks = Some(recording.inputs.len());
+                                        ghost_saved = true;
+                                    }
+                                    Err(e) => eprintln!("Warning: no se pudo guardar el fantasma en {}: {}", path.display(), e),
+                                }
+                            }
+                            // las repeticiones no entran en la tabla: esa partida no la jugó nadie ahora
+                            if replay.is_none() && records_path().is_some_and(|path| RecordTable::load(&path).qualifies(&def.name, &res)) {
+                                // descartamos las letras tecleadas al jugar (WASD) antes de pedir las iniciales
+                                while get_char_pressed().is_some() {}
+                                initials = Some(String::new());
+                            }
+                            results = Some(res);
+                            // Jugador gana: paramos sonido de fondo inmediatamente y reproducimos final (una sola vez)
+                            if !final_played {
+                                if let Some(bg_ref) = bg_sound_opt.as_ref() {
+                                    // paramos fondo justo antes de reproducir el final
+                                    stop_sound(bg_ref);
+                                    bg_playing = false;
+                                }
+                                // ya no queremos que el fondo vuelva a iniciarse
+                                bg_should_play = false;
+
+                                // reproducir final
+                                if let Some(final_ref) = final_sound_opt.as_ref() {
+                                    play_sound(final_ref, PlaySoundParams { looped: false, volume: 1.0 });
+                                }
+                                final_played = true;
+                            }
+
+                            set_cursor_grab(false);
+                            show_mouse(true);
+                        }
+                        GameEvent::Lost => {
+                            set_cursor_grab(false);
+                            show_mouse(true);
+                        }
+                    }
+                }
+                // terminada la partida no se simulan (ni graban) más pasos
+                if game.finished() {
+                    accumulator = 0.0;
+                    break;
+                }
+            }
+        }
+
+        // Permitir que la terminal pida volver al menú (si se escribió "menu" / "m" / "return")
+        if def.terminal_commands && RETURN_TO_MENU.swap(false, Ordering::Relaxed) {
+            // aseguramos cursor visible antes de salir
+            set_cursor_grab(false);
+            show_mouse(true);
+            break; // salimos del nivel y volvemos al menú principal
+        }
+
+        // Iniciales para la tabla de récords: letras o números, Backspace borra, Enter guarda
+        if let Some(text) = &mut initials {
+            while let Some(c) = get_char_pressed() {
+                if c.is_ascii_alphanumeric() && text.len() < MAX_INITIALS {
+                    text.push(c.to_ascii_uppercase());
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                text.pop();
+            }
+            if is_key_pressed(KeyCode::Enter) && !text.is_empty() {
+                if let (Some(path), Some(res)) = (records_path(), &results) {
+                    let mut table = RecordTable::load(&path);
+                    table.submit(&def.name, text, res);
+                    match table.save(&path) {
+                        Ok(()) => record_saved = true,
+                        Err(e) => eprintln!("Warning: no se pudieron guardar los récords en {}: {}", path.display(), e),
+                    }
+                }
+                initials = None;
+            }
+        }
+
+        // RAYCAST (paredes + sprites) en el framebuffer de software.
+        // La celda 3 es muro solo mientras queden amigos por encontrar.
+        let fb_w = ((screen_width() * RENDER_SCALE) as usize).max(1);
+        let fb_h = ((screen_height() * RENDER_SCALE) as usize).max(1);
+        // cámara entre el paso anterior y el actual según lo que sobró en el acumulador
+        let alpha = if paused || game.finished() { 1.0 } else { accumulator / TICK_DT };
+        let view = game.interpolated_cam(alpha);
+        // paredes, sprites y luces se animan con el reloj de la partida: se congelan en pausa y
+        // una repetición se ve igual que la partida original
+        let anim_t = game.interpolated_time(alpha);
+        // el fantasma va por el mismo paso que la partida
+        let tick = recording.inputs.len();
+        let ghost_billboard = match (&ghost, &assets.ghost) {
+            (Some(g), Some(sprite)) => {
+                let cam = g.cam_at(tick, alpha);
+                vec![Billboard { pos: cam.pos, facing: cam.dir, walking: g.walking_at(tick), sprite }]
+            }
+            _ => Vec::new(),
+        };
+        // tope de cuadros: entre uno y otro se vuelve a mostrar la última imagen, sin dormir el hilo
+        let now = get_time();
+        let resized = fb.width != fb_w || fb.height != fb_h;
+        if resized || MAX_FPS.is_none_or(|max| now - last_render >= 1.0 / max as f64) {
+            last_render = now;
+            if resized {
+                fb = Framebuffer::new(fb_w, fb_h);
+                screen_tex = Texture2D::from_rgba8(fb_w as u16, fb_h as u16, &fb.to_rgba8());
+                screen_tex.set_filter(FilterMode::Nearest);
+            }
+            render_frame(&mut fb, &view, &game.level, &assets, &ghost_billboard, anim_t);
+            screen_tex.update_from_bytes(fb_w as u32, fb_h as u32, &fb.to_rgba8());
+        }
+        // F12: captura del cuadro del raycaster (sin HUD) en captura.png
+        if is_key_pressed(KeyCode::F12) {
+            fb.save_png("captura.png");
+            println!("Captura guardada en captura.png");
+        }
+        draw_texture_ex(
+            &screen_tex,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(screen_width(), screen_height())),
+                ..Default::default()
+            },
+        );
+
+        // Minimap y HUD (el minimapa refleja el estado de la partida)
+        draw_minimap(def, &game.level, &view, ghost.as_ref().map(|g| (g, tick)));
+        let fps = get_fps();
+        let controls = if def.doors.is_empty() { "Esc para pausar" } else { "E para abrir puertas, Esc para pausar" };
+        draw_text(&format!("{} — {}", def.name, controls), 10.0, 40.0, 18.0, WHITE);
+
+        if let Some((text, until)) = &message {
+            if get_time() < *until {
+                let tw = measure_text(text, None, 26, 1.0);
+                draw_text(text, screen_width() / 2.0 - tw.width / 2.0, screen_height() - 40.0, 26.0, ORANGE);
+            } else {
+                message = None;
+            }
+        }
+
+        // destello rojo al recibir daño, que se apaga al comienzo de la invulnerabilidad
+        let flash = (game.level.invulnerable - (INVULNERABLE_SECS - DAMAGE_FLASH_SECS)) / DAMAGE_FLASH_SECS;
+        if flash > 0.0 {
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color { r: 0.8, g: 0.0, b: 0.0, a: 0.45 * flash });
+        }
+
+        if !game.level.won && !game.level.lost {
+            let mut hud = format!("Amigos por encontrar: {}", game.level.coins);
+            // puntos solo en los niveles con monedas; vida en los que tienen cebollas, enemigos o peligros
+            if def.items.iter().any(|i| matches!(i.effect, ItemEffect::Score(_))) {
+                hud += &format!(" | Puntos: {}", game.level.score);
+            }
+            if def.has_damage() || def.items.iter().any(|i| matches!(i.effect, ItemEffect::Health(_))) {
+                hud += &format!(" | Vida: {}", game.level.health);
+            }
+            hud += &format!(" | Tiempo: {}", format_time(game.level.elapsed));
+            if let Some(par) = def.par_time {
+                hud += &format!(" (par {})", format_time(par));
+            }
+            if let Some(g) = &ghost {
+                hud += &format!(" | Fantasma: {}", format_time(g.duration()));
+            }
+            if let Some(boost) = game.level.speed_boost {
+                hud += &format!(" | Velocidad x{:.1} ({:.0}s)", boost.factor, boost.remaining.ceil());
+            }
+            hud += &format!(" | FPS: {:.0}", fps);
+            if let Some(rec) = replay {
+                let left = rec.inputs.len().saturating_sub(recording.inputs.len());
+                hud += if left > 0 { " | REPETICIÓN" } else { " | REPETICIÓN (terminada)" };
+            }
+            draw_text(&hud, 10.0, 20.0, 22.0, YELLOW);
+        } else {
+            let sw = screen_width();
+            let sh = screen_height();
+            draw_rectangle(0.0, 0.0, sw, sh, Color { r: 0.0, g: 0.0, b: 0.0, a: 0.7 });
+            let (msg, color) = if game.level.won { ("¡Has ganado!", GOLD) } else { ("¡Has perdido!", RED) };
+            // al ganar, el título sube para dejar lugar al desglose del puntaje
+            let mut y = if results.is_some() { sh * 0.22 } else { sh / 2.0 };
+            let tw = measure_text(msg, None, 60, 1.0);
+            draw_text(msg, sw/2.0 - tw.width/2.0, y, 60.0, color);
+            if let Some(res) = &results {
+                y += 20.0;
+                for line in &res.lines {
+                    y += 30.0;
+                    draw_text(&line.label, sw / 2.0 - 220.0, y, 26.0, WHITE);
+                    let pts = format!("{:+}", line.points);
+                    let pw = measure_text(&pts, None, 26, 1.0);
+                    draw_text(&pts, sw / 2.0 + 220.0 - pw.width, y, 26.0, if line.points < 0 { RED } else { WHITE });
+                }
+                y += 40.0;
+                let total = format!("Puntaje: {}", res.total);
+                let tw = measure_text(&total, None, 36, 1.0);
+                draw_text(&total, sw / 2.0 - tw.width / 2.0, y, 36.0, GOLD);
+                let record_line = match &initials {
+                    Some(text) => Some(format!("¡Nuevo récord! Iniciales: {}_ (Enter para guardar)", text)),
+                    None if record_saved => Some("Récord guardado".to_string()),
+                    None => None,
+                };
+                if let Some(line) = record_line {
+                    y += 36.0;
+                    let lw = measure_text(&line, None, 26, 1.0);
+                    draw_text(&line, sw / 2.0 - lw.width / 2.0, y, 26.0, ORANGE);
+                }
+                let ghost_line = match &ghost {
+                    Some(g) if res.time < g.duration() => Some(format!("¡Le ganaste a tu fantasma por {:.1} s!", g.duration() - res.time)),
+                    Some(g) => Some(format!("Tu fantasma llegó {:.1} s antes", res.time - g.duration())),
+                    None if ghost_saved => Some("Nuevo mejor recorrido: será tu fantasma".to_string()),
+                    None => None,
+                };
+                if let Some(line) = ghost_line {
+                    y += 32.0;
+                    let lw = measure_text(&line, None, 24, 1.0);
+                    draw_text(&line, sw / 2.0 - lw.width / 2.0, y, 24.0, SKYBLUE);
+                }
+            }
+            y += 40.0;
+
+            // al perder se puede reintentar: el nivel vuelve a empezar desde cero
+            let menu_y = if game.level.lost { y + BUTTON_STEP } else { y };
+            if game.level.lost && overlay_button(sw, y, "Reintentar") {
+                game = Game::new(def);
+                recording = Recording::new(&def.name);
+                accumulator = 0.0;
+                message = None;
+                if mouse_look {
+                    set_cursor_grab(true);
+                    show_mouse(false);
+                }
+                last_mouse_x = mouse_position().0;
+            } else if overlay_button(sw, menu_y, "Volver al menú") {
+                set_cursor_grab(false);
+                show_mouse(true);
+                break;
+            }
+        }
+
+        if paused && !game.level.won && !game.level.lost {
+            let sw = screen_width();
+            let sh = screen_height();
+            draw_rectangle(0.0, 0.0, sw, sh, Color { r: 0.0, g: 0.0, b: 0.0, a: 0.5 });
+            let msg = "Pausado — presiona ESC para continuar";
+            let tw = measure_text(msg, None, 32, 1.0);
+            draw_text(msg, sw/2.0 - tw.width/2.0, sh/2.0, 32.0, WHITE);
+
+            // detectar click en el botón
+            if overlay_button(sw, sh / 2.0 + 40.0, "Volver al menú") {
+                // asegurar cursor visible antes de salir
+                set_cursor_grab(false);
+                show_mouse(true);
+                // salir del nivel: main.rs recibirá el control y volverá al menú principal
+                break;
+            }
+        }
+
+        next_frame().await;
+    }
+    recording
+}
+
+/// Teclas de movimiento que están apretadas (W/S o flechas arriba/abajo avanzan y retroceden,
+/// A/D o flechas izquierda/derecha se desplazan de costado).
+fn held_input() -> InputFrame {
+    InputFrame {
+        forward: is_key_down(KeyCode::W) || is_key_down(KeyCode::Up),
+        back: is_key_down(KeyCode::S) || is_key_down(KeyCode::Down),
+        left: is_key_down(KeyCode::A) || is_key_down(KeyCode::Left),
+        right: is_key_down(KeyCode::D) || is_key_down(KeyCode::Right),
+        ..Default::default()
+    }
+}
+
+/// Botón centrado horizontalmente en `btn_y`, debajo del mensaje de una pantalla superpuesta
+/// (pausa, victoria, derrota). Devuelve `true` si se hizo clic en él.
+fn overlay_button(sw: f32, btn_y: f32, label: &str) -> bool {
+    let btn_w = 220.0;
+    let btn_h = 48.0;
+    let btn_x = sw / 2.0 - btn_w / 2.0;
+
+    let btn_rect = Rect::new(btn_x, btn_y, btn_w, btn_h);
+
+    // cambiar apariencia si el mouse está encima
+    let (mx, my) = mouse_position();
+    if btn_rect.contains(vec2(mx, my)) {
+        draw_rectangle(btn_x - 4.0, btn_y - 4.0, btn_w + 8.0, btn_h + 8.0, GRAY);
+    } else {
+        draw_rectangle(btn_x - 2.0, btn_y - 2.0, btn_w + 4.0, btn_h + 4.0, DARKGRAY);
+    }
+
+    draw_rectangle(btn_x, btn_y, btn_w, btn_h, DARKBLUE);
+    let lt = measure_text(label, None, 28, 1.0);
+    draw_text(label, btn_x + btn_w / 2.0 - lt.width / 2.0, btn_y + btn_h / 2.0 + 10.0, 28.0, WHITE);
+
+    is_mouse_button_pressed(MouseButton::Left) && btn_rect.contains(vec2(mx, my))
+}
+
+/// Minimapa del nivel con el jugador; con `ghost`, también el rastro del fantasma hasta el paso dado.
+fn draw_minimap(def: &LevelDef, state: &LevelState, cam: &Camera, ghost: Option<(&Ghost, usize)>) {
+    let ox = 10.0;
+    let oy = 60.0;
+
+    draw_rectangle(ox - 2.0, oy - 2.0, state.map.width as f32 * MINIMAP_SCALE + 4.0, state.map.height as f32 * MINIMAP_SCALE + 4.0, Color { r: 0.05, g: 0.05, b: 0.05, a: 0.8 });
+
+    for y in 0..state.map.height {
+        for x in 0..state.map.width {
+            let cell = state.map.get(x as i32, y as i32).unwrap_or(0);
+            // Mostrar la salida (3) como muro (rojo) solo si quedan monedas; si no, mostrarla como vacía.
+            let color = if is_wall(cell) {
+                GREEN
+            } else if let Some(door) = state.door_at(x as i32, y as i32) {
+                // puertas: naranja si tienen llave/evento pendiente, marrón que se desvanece al abrirse
+                if state.door_lock(def, door).is_some() {
+                    ORANGE
+                } else {
+                    Color { a: 1.0 - door.open * 0.8, ..BROWN }
+                }
+            } else if cell == CELL_EXIT {
+                if state.coins > 0 { RED } else { BLACK }
+            } else if def.collectible(cell).is_some() {
+                YELLOW
+            } else if def.hazard_at(vec2(x as f32, y as f32)).is_some() {
+                // peligros: oliva, para poder esquivarlos
+                Color { r: 0.35, g: 0.4, b: 0.1, a: 1.0 }
+            } else {
+                BLACK
+            };
+            draw_rectangle(ox + x as f32 * MINIMAP_SCALE, oy + y as f32 * MINIMAP_SCALE, MINIMAP_SCALE, MINIMAP_SCALE, color);
+        }
+    }
+
+    // objetos sin recoger, como puntos de color según su efecto
+    for p in &state.pickups {
+        let PickupKind::Item(id) = p.kind else { continue };
+        let color = match def.item(id).map(|i| &i.effect) {
+            Some(ItemEffect::Score(_)) => GOLD,
+            Some(ItemEffect::Key(_)) => WHITE,
+            Some(ItemEffect::Health(_)) => PINK,
+            Some(ItemEffect::Speed { .. }) => SKYBLUE,
+            None => continue,
+        };
+        draw_circle(ox + p.pos.x * MINIMAP_SCALE, oy + p.pos.y * MINIMAP_SCALE, MINIMAP_SCALE * 0.25, color);
+    }
+
+    // enemigos
+    for enemy in &state.enemies {
+        draw_circle(ox + enemy.pos.x * MINIMAP_SCALE, oy + enemy.pos.y * MINIMAP_SCALE, MINIMAP_SCALE * 0.35, MAGENTA);
+    }
+
+    // rastro del fantasma: por dónde pasó hasta ahora y dónde va
+    if let Some((g, tick)) = ghost {
+        let trail = Color { a: 0.6, ..SKYBLUE };
+        let upto = tick.min(g.path.len() - 1);
+        let points: Vec<Vec2> = g.path[..=upto].iter().step_by(GHOST_TRAIL_STEP).chain([&g.path[upto]]).map(|c| c.pos).collect();
+        for w in points.windows(2) {
+            draw_line(ox + w[0].x * MINIMAP_SCALE, oy + w[0].y * MINIMAP_SCALE, ox + w[1].x * MINIMAP_SCALE, oy + w[1].y * MINIMAP_SCALE, 1.5, trail);
+        }
+        let head = g.path[upto].pos;
+        draw_circle(ox + head.x * MINIMAP_SCALE, oy + head.y * MINIMAP_SCALE, MINIMAP_SCALE * 0.35, Color { a: 0.8, ..SKYBLUE });
+    }
+
+    let px = ox + cam.pos.x * MINIMAP_SCALE;
+    let py = oy + cam.pos.y * MINIMAP_SCALE;
+    draw_circle(px, py, MINIMAP_SCALE * 0.35, BLUE);
+
+    let lx = px + cam.dir.x * MINIMAP_SCALE * 1.2;
+    let ly = py + cam.dir.y * MINIMAP_SCALE * 1.2;
+    draw_line(px, py, lx, ly, 1.5, WHITE);
+}
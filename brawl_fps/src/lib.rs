@@ -0,0 +1,9 @@
+pub mod enemigos;
+pub mod juego;
+pub mod luces;
+pub mod nivel;
+pub mod puntaje;
+pub mod raycaster;
+pub mod records;
+pub mod repeticion;
+pub mod simulacion;
@@ -0,0 +1,146 @@
+use macroquad::prelude::*;
+
+use crate::nivel::{LevelDef, LevelState, LightSource, CELL_EXIT, CELL_WALL};
+
+// Iluminación por celdas: al cargar el nivel se "hornea" cuánto aporta cada luz a cada celda
+// (caída con la distancia y sombra de las paredes). En cada cuadro solo se suman esos aportes
+// con el parpadeo del momento y se apagan las luces de amigos ya recogidos, así el costo por
+// cuadro es lineal en celdas × luces y el render solo tiene que muestrear la cuadrícula.
+
+/// Paso (en celdas) con el que se recorre el segmento luz→celda al comprobar si lo tapa una pared.
+const SHADOW_STEP: f32 = 0.1;
+
+/// Celda (y su código) de la que cuelga una luz.
+#[derive(Clone, Copy)]
+struct Anchor {
+    x: i32,
+    y: i32,
+    code: i32,
+}
+
+/// Una luz ya horneada: su aporte (0..1) por celda, antes de color, intensidad y parpadeo.
+struct BakedLight {
+    color: Vec3,
+    flicker: f32,
+    /// Semilla del parpadeo, para que dos antorchas no titilen al unísono.
+    seed: f32,
+    /// Celda de la que cuelga la luz (`None` = luz fija).
+    attached: Option<Anchor>,
+    weights: Vec<f32>,
+}
+
+/// Luces de un nivel horneadas sobre su cuadrícula.
+pub struct LightMap {
+    width: usize,
+    height: usize,
+    ambient: f32,
+    lights: Vec<BakedLight>,
+}
+
+impl LightMap {
+    pub fn bake(def: &LevelDef) -> Self {
+        let grid = &def.grid;
+        let mut lights = Vec::new();
+        for light in &def.lights {
+            let color = vec3(light.color.0 as f32, light.color.1 as f32, light.color.2 as f32) / 255.0 * light.intensity;
+            // una luz fija, o una por cada celda con el código indicado
+            let sources: Vec<(Vec2, Option<Anchor>)> = match light.source {
+                LightSource::Pos(p) => vec![(p, None)],
+                LightSource::Cell(code) => (0..grid.height as i32)
+                    .flat_map(|y| (0..grid.width as i32).map(move |x| (x, y)))
+                    .filter(|&(x, y)| grid.get(x, y) == Some(code))
+                    .map(|(x, y)| (vec2(x as f32 + 0.5, y as f32 + 0.5), Some(Anchor { x, y, code })))
+                    .collect(),
+            };
+            for (pos, attached) in sources {
+                let mut weights = vec![0.0; grid.width * grid.height];
+                for y in 0..grid.height {
+                    for x in 0..grid.width {
+                        let target = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                        let falloff = (1.0 - pos.distance(target) / light.radius).max(0.0);
+                        if falloff > 0.0 && !occluded(def, pos, target) {
+                            weights[y * grid.width + x] = falloff * falloff;
+                        }
+                    }
+                }
+                let seed = pos.x * 12.9898 + pos.y * 78.233;
+                lights.push(BakedLight { color, flicker: light.flicker, seed, attached, weights });
+            }
+        }
+        Self { width: grid.width, height: grid.height, ambient: def.ambient, lights }
+    }
+
+    /// Suma la luz de todas las fuentes activas en el instante `t` (segundos).
+    /// Las luces de amigos se apagan al recogerlos; la de la salida se enciende al abrirla.
+    pub fn evaluate(&self, state: &LevelState, t: f32) -> LightGrid {
+        let mut cells = vec![Vec3::splat(self.ambient); self.width * self.height];
+        for light in &self.lights {
+            if let Some(a) = light.attached
+                && (state.map.get(a.x, a.y) != Some(a.code) || (a.code == CELL_EXIT && state.coins > 0))
+            {
+                continue;
+            }
+            let color = light.color * flicker(t, light.seed, light.flicker);
+            for (cell, &w) in cells.iter_mut().zip(&light.weights) {
+                *cell += color * w;
+            }
+        }
+        let uniform = self.lights.is_empty() && self.ambient == 1.0;
+        LightGrid { width: self.width, height: self.height, cells, uniform }
+    }
+}
+
+/// Factor de intensidad (entre `1 - amount` y 1) de una luz que parpadea. Determinista en `t`.
+fn flicker(t: f32, seed: f32, amount: f32) -> f32 {
+    if amount <= 0.0 {
+        return 1.0;
+    }
+    let n = (t * 7.3 + seed).sin() * 0.6 + (t * 17.9 + seed * 1.7).sin() * 0.4;
+    1.0 - amount * (0.5 + 0.5 * n)
+}
+
+/// Si una pared (distinta de la celda destino) tapa el segmento `from`→`to`.
+/// Se usa la cuadrícula original: la salida cerrada no proyecta sombra.
+fn occluded(def: &LevelDef, from: Vec2, to: Vec2) -> bool {
+    let target = (to.x.floor() as i32, to.y.floor() as i32);
+    let steps = (from.distance(to) / SHADOW_STEP).ceil() as usize;
+    (1..steps).any(|i| {
+        let p = from.lerp(to, i as f32 / steps as f32);
+        let cell = (p.x.floor() as i32, p.y.floor() as i32);
+        cell != target && def.grid.get(cell.0, cell.1) == Some(CELL_WALL)
+    })
+}
+
+/// Luz de cada celda en un cuadro concreto (RGB, 1 = sin cambio, puede pasar de 1).
+pub struct LightGrid {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec3>,
+    uniform: bool,
+}
+
+impl LightGrid {
+    /// Si la luz es 1 en todas partes (nivel sin luces): el render puede saltarse el muestreo.
+    pub fn is_uniform(&self) -> bool {
+        self.uniform
+    }
+
+    /// Luz en un punto del mundo, interpolada entre los centros de las celdas vecinas.
+    pub fn sample(&self, pos: Vec2) -> Vec3 {
+        if self.uniform {
+            return Vec3::ONE;
+        }
+        let gx = pos.x - 0.5;
+        let gy = pos.y - 0.5;
+        let (x0, y0) = (gx.floor(), gy.floor());
+        let (fx, fy) = (gx - x0, gy - y0);
+        let cell = |x: f32, y: f32| {
+            let cx = (x as i32).clamp(0, self.width as i32 - 1) as usize;
+            let cy = (y as i32).clamp(0, self.height as i32 - 1) as usize;
+            self.cells[cy * self.width + cx]
+        };
+        let top = cell(x0, y0).lerp(cell(x0 + 1.0, y0), fx);
+        let bottom = cell(x0, y0 + 1.0).lerp(cell(x0 + 1.0, y0 + 1.0), fx);
+        top.lerp(bottom, fy)
+    }
+}
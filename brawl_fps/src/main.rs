@@ -1,12 +1,56 @@
 use macroquad::prelude::*;
 
-mod nivel1;
-mod nivel2;
+use brawl_fps::juego;
+use brawl_fps::nivel::LevelDef;
+use brawl_fps::puntaje::format_time;
+use brawl_fps::records::{records_path, RecordTable};
+use brawl_fps::repeticion::Recording;
+
+/// Carpeta de los niveles: cada `.txt` es un botón del menú, en orden alfabético.
+const LEVEL_DIR: &str = "niveles";
 
 enum MenuState {
     Main,
-    Level1,
-    Level2,
+    Level(usize),
+    /// Tabla de récords, leída del disco al entrar a la pantalla.
+    Records(RecordTable),
+    /// Repetición de una partida grabada en el nivel de ese índice.
+    Replay(usize, Recording),
+}
+
+/// Opciones de línea de comandos:
+///   --grabar <archivo>   guarda la entrada de cada partida en `archivo` (la última jugada queda)
+///   --repetir <archivo>  antes del menú, reproduce la partida grabada en `archivo`
+///   --fantasma           empieza con el modo fantasma activado (también se cambia en el menú)
+#[derive(Default)]
+struct Options {
+    record_to: Option<String>,
+    replay_from: Option<String>,
+    ghost: bool,
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--grabar" => &mut opts.record_to,
+            "--repetir" => &mut opts.replay_from,
+            "--fantasma" => {
+                opts.ghost = true;
+                continue;
+            }
+            _ => {
+                eprintln!("Warning: argumento desconocido `{}` (se aceptan --grabar <archivo>, --repetir <archivo> y --fantasma)", arg);
+                continue;
+            }
+        };
+        match args.next() {
+            Some(path) => *slot = Some(path),
+            None => eprintln!("Warning: falta el archivo después de `{}`", arg),
+        }
+    }
+    opts
 }
 
 #[macroquad::main("Shrek Find and Rescue")]
@@ -14,7 +58,32 @@ async fn main() {
     let title_tex: Texture2D = load_texture("img/menu.png").await.unwrap();
     title_tex.set_filter(FilterMode::Nearest);
 
+    // Plantillas de los niveles: se cargan una vez y cada partida clona su propio estado.
+    // Los que no cargan quedan afuera del menú.
+    let levels: Vec<LevelDef> = level_files()
+        .iter()
+        .filter_map(|path| match LevelDef::load(path) {
+            Ok(def) => Some(def),
+            Err(e) => {
+                eprintln!("Error: no se pudo cargar el nivel: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    let opts = parse_args();
+    // modo fantasma: correr contra la mejor partida anterior de cada nivel
+    let mut ghost_mode = opts.ghost;
     let mut state = MenuState::Main;
+    if let Some(path) = &opts.replay_from {
+        match Recording::load(path) {
+            Ok(rec) => match levels.iter().position(|def| def.name == rec.level) {
+                Some(i) => state = MenuState::Replay(i, rec),
+                None => eprintln!("Error: la repetición {} es del nivel `{}`, que no está disponible", path, rec.level),
+            },
+            Err(e) => eprintln!("Error: no se pudo cargar la repetición: {}", e),
+        }
+    }
 
     loop {
         clear_background(BLACK);
@@ -44,84 +113,77 @@ async fn main() {
                 let right_x_factor = 0.75_f32;
                 let center_x = screen_width() * right_x_factor;
 
-                // calcular posición Y para centrar los 3 botones verticalmente
-                let total_h = btn_h * 3.0 + spacing * 2.0;
+                // Botones en orden: un nivel por botón (alternando azul y morado), verde, gris
+                // (interruptor) y rojo
+                let level_colors = [DARKBLUE, Color::new(0.55, 0.15, 0.6, 1.0)];
+                let ghost_label = if ghost_mode { "Fantasma: Sí" } else { "Fantasma: No" };
+                let mut buttons: Vec<(&str, Color)> =
+                    levels.iter().enumerate().map(|(i, def)| (def.name.as_str(), level_colors[i % level_colors.len()])).collect();
+                buttons.push(("Récords", Color::new(0.1, 0.45, 0.2, 1.0)));
+                buttons.push((ghost_label, Color::new(0.35, 0.35, 0.4, 1.0)));
+                buttons.push(("Salir", RED));
+
+                // calcular posición Y para centrar los botones verticalmente
+                let total_h = btn_h * buttons.len() as f32 + spacing * (buttons.len() - 1) as f32;
                 let start_y = screen_height() / 2.0 - total_h / 2.0;
+                let btn_x = center_x - btn_w / 2.0;
 
-                let btn1_x = center_x - btn_w / 2.0;
-                let btn1_y = start_y;
-                let btn2_x = btn1_x;
-                let btn2_y = btn1_y + btn_h + spacing;
-                let btn3_x = btn1_x;
-                let btn3_y = btn2_y + btn_h + spacing;
-
-                let button1 = Rect::new(btn1_x, btn1_y, btn_w, btn_h);
-                let button2 = Rect::new(btn2_x, btn2_y, btn_w, btn_h);
-                let button3 = Rect::new(btn3_x, btn3_y, btn_w, btn_h);
-
-                // Colores: azul, morado (personalizado), rojo
-                let color_btn1 = DARKBLUE;
-                let color_btn2 = Color::new(0.55, 0.15, 0.6, 1.0); // morado
-                let color_btn3 = RED;
-
-                // hover color (ligero aclarado)
-                let hover_color = Color::new(0.85, 0.85, 0.85, 1.0);
-
-                // dibujar sombras/contornos si el mouse está encima (pequeño efecto)
-                let (mx, my) = mouse_position();
-
-                // Botón 1
-                if button1.contains(vec2(mx, my)) {
-                    draw_rectangle(button1.x - 6.0, button1.y - 6.0, button1.w + 12.0, button1.h + 12.0, hover_color);
-                } else {
-                    draw_rectangle(button1.x - 2.0, button1.y - 2.0, button1.w + 4.0, button1.h + 4.0, DARKGRAY);
-                }
-                draw_rectangle(button1.x, button1.y, button1.w, button1.h, color_btn1);
-                let label1 = "Nivel 1";
-                let mt1 = measure_text(label1, None, 30, 1.0);
-                draw_text(label1, button1.x + button1.w / 2.0 - mt1.width / 2.0, button1.y + button1.h / 2.0 + 10.0, 30.0, WHITE);
-
-                // Botón 2
-                if button2.contains(vec2(mx, my)) {
-                    draw_rectangle(button2.x - 6.0, button2.y - 6.0, button2.w + 12.0, button2.h + 12.0, hover_color);
-                } else {
-                    draw_rectangle(button2.x - 2.0, button2.y - 2.0, button2.w + 4.0, button2.h + 4.0, DARKGRAY);
-                }
-                draw_rectangle(button2.x, button2.y, button2.w, button2.h, color_btn2);
-                let label2 = "Nivel 2";
-                let mt2 = measure_text(label2, None, 30, 1.0);
-                draw_text(label2, button2.x + button2.w / 2.0 - mt2.width / 2.0, button2.y + button2.h / 2.0 + 10.0, 30.0, WHITE);
-
-                // Botón 3 (Salir)
-                if button3.contains(vec2(mx, my)) {
-                    draw_rectangle(button3.x - 6.0, button3.y - 6.0, button3.w + 12.0, button3.h + 12.0, hover_color);
-                } else {
-                    draw_rectangle(button3.x - 2.0, button3.y - 2.0, button3.w + 4.0, button3.h + 4.0, DARKGRAY);
+                let mut clicked = None;
+                for (i, (label, color)) in buttons.iter().enumerate() {
+                    let rect = Rect::new(btn_x, start_y + i as f32 * (btn_h + spacing), btn_w, btn_h);
+                    if menu_button(rect, label, *color) {
+                        clicked = Some(i);
+                    }
                 }
-                draw_rectangle(button3.x, button3.y, button3.w, button3.h, color_btn3);
-                let label3 = "Salir";
-                let mt3 = measure_text(label3, None, 30, 1.0);
-                draw_text(label3, button3.x + button3.w / 2.0 - mt3.width / 2.0, button3.y + button3.h / 2.0 + 10.0, 30.0, WHITE);
-
-                // manejar clicks
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    if button1.contains(vec2(mx, my)) {
-                        state = MenuState::Level1;
-                    } else if button2.contains(vec2(mx, my)) {
-                        state = MenuState::Level2;
-                    } else if button3.contains(vec2(mx, my)) {
-                        std::process::exit(0);
+
+                // manejar clicks: primero los niveles, después récords, fantasma y salir
+                match clicked {
+                    Some(i) if i < levels.len() => state = MenuState::Level(i),
+                    Some(i) if i == levels.len() => {
+                        let table = records_path().map(|path| RecordTable::load(&path)).unwrap_or_default();
+                        state = MenuState::Records(table);
                     }
+                    Some(i) if i == levels.len() + 1 => ghost_mode = !ghost_mode,
+                    Some(_) => std::process::exit(0),
+                    None => {}
+                }
+            }
+
+            MenuState::Records(ref table) => {
+                draw_texture_ex(
+                    &title_tex,
+                    0.0,
+                    0.0,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(screen_width(), screen_height())),
+                        ..Default::default()
+                    },
+                );
+                draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color { r: 0.0, g: 0.0, b: 0.0, a: 0.75 });
+                draw_records(table, &levels);
+
+                let btn_w = 220.0;
+                let btn_h = 56.0;
+                let back = Rect::new(screen_width() / 2.0 - btn_w / 2.0, screen_height() - btn_h - 30.0, btn_w, btn_h);
+                if menu_button(back, "Volver", DARKBLUE) || is_key_pressed(KeyCode::Escape) {
+                    state = MenuState::Main;
                 }
             }
 
-            MenuState::Level1 => {
-                nivel1::run_level1().await;
+            MenuState::Level(i) => {
+                let recording = juego::run_level(&levels[i], None, ghost_mode).await;
+                if let Some(path) = &opts.record_to {
+                    match recording.save(std::path::Path::new(path)) {
+                        Ok(()) => println!("Repetición guardada en {}", path),
+                        Err(e) => eprintln!("Warning: no se pudo guardar la repetición en {}: {}", path, e),
+                    }
+                }
                 state = MenuState::Main; // volver al menú cuando termine
             }
 
-            MenuState::Level2 => {
-                nivel2::run_level2().await;
+            MenuState::Replay(i, ref rec) => {
+                juego::run_level(&levels[i], Some(rec), ghost_mode).await;
                 state = MenuState::Main;
             }
         }
@@ -129,3 +191,72 @@ async fn main() {
         next_frame().await;
     }
 }
+
+/// Archivos `.txt` de `LEVEL_DIR`, ordenados por nombre.
+fn level_files() -> Vec<String> {
+    let entries = match std::fs::read_dir(LEVEL_DIR) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: no se pudo leer la carpeta {}: {}", LEVEL_DIR, e);
+            return Vec::new();
+        }
+    };
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Dibuja un botón del menú (con contorno claro si el mouse está encima). Devuelve `true` si
+/// se hizo clic en él.
+fn menu_button(rect: Rect, label: &str, color: Color) -> bool {
+    // hover color (ligero aclarado)
+    let hover_color = Color::new(0.85, 0.85, 0.85, 1.0);
+
+    // dibujar sombras/contornos si el mouse está encima (pequeño efecto)
+    let (mx, my) = mouse_position();
+    let hovered = rect.contains(vec2(mx, my));
+    if hovered {
+        draw_rectangle(rect.x - 6.0, rect.y - 6.0, rect.w + 12.0, rect.h + 12.0, hover_color);
+    } else {
+        draw_rectangle(rect.x - 2.0, rect.y - 2.0, rect.w + 4.0, rect.h + 4.0, DARKGRAY);
+    }
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+    let mt = measure_text(label, None, 30, 1.0);
+    draw_text(label, rect.x + rect.w / 2.0 - mt.width / 2.0, rect.y + rect.h / 2.0 + 10.0, 30.0, WHITE);
+
+    hovered && is_mouse_button_pressed(MouseButton::Left)
+}
+
+/// Pantalla de récords: por cada nivel, el mejor tiempo y los mejores puntajes.
+fn draw_records(table: &RecordTable, levels: &[LevelDef]) {
+    let sw = screen_width();
+    let title = "Récords";
+    let tw = measure_text(title, None, 56, 1.0);
+    draw_text(title, sw / 2.0 - tw.width / 2.0, 70.0, 56.0, GOLD);
+
+    // una columna por nivel
+    let names: Vec<&str> = levels.iter().map(|def| def.name.as_str()).collect();
+    let col_w = sw / names.len().max(1) as f32;
+    for (i, name) in names.iter().enumerate() {
+        let x = col_w * i as f32 + col_w / 2.0 - 130.0;
+        let mut y = 130.0;
+        draw_text(name, x, y, 34.0, WHITE);
+        let Some(rec) = table.level(name).filter(|r| !r.scores.is_empty()) else {
+            draw_text("Sin récords todavía", x, y + 40.0, 24.0, GRAY);
+            continue;
+        };
+        if let Some(best) = &rec.best_time {
+            y += 40.0;
+            draw_text(&format!("Mejor tiempo: {} ({})", format_time(best.time), best.initials), x, y, 24.0, YELLOW);
+        }
+        y += 10.0;
+        for (rank, e) in rec.scores.iter().enumerate() {
+            y += 32.0;
+            draw_text(&format!("{}. {:<3}  {:>6}  {}", rank + 1, e.initials, e.score, format_time(e.time)), x, y, 24.0, WHITE);
+        }
+    }
+}
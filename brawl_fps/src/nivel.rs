@@ -0,0 +1,1682 @@
+use macroquad::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::enemigos::Enemy;
+
+// Formato de archivo de nivel (texto plano):
+//
+//   # comentario
+//   nombre = Nivel 1
+//   spawn = 1.5 10.5
+//   direccion = 1 0
+//   musica = img/fondo.wav
+//   textura planicie = img/planicie.png
+//   textura antorcha = img/antorcha.png
+//   animacion antorcha = cuadros=4 columnas=4 fps=8
+//   sonido moneda = img/moneda.wav
+//   pared = planicie
+//   pared 2 = bosque
+//   pared 3 = textura=castillo norte=puerta
+//   pared 4 = textura=vidrio opacidad=0.4
+//   amigos_requeridos = 1
+//   tiempo_par = 90
+//   radio_jugador = 0.25
+//   amigo 2 = sprite=burro sonido=moneda escala=0.45 bob=2.0,0.06 llave=dorada
+//   amigo 4 = sprite=gato angulos=8 quieto=2 camina=4 fps=6 mira=0,1
+//   puerta 5 = textura=puerta llave=dorada evento=amigos
+//   salida = sprite=fiona escala=0.85 bob=2.4,0.08
+//   fantasma = sprite=shrek angulos=8 quieto=1 camina=2 opacidad=0.4
+//   objeto 1 = tipo=moneda sprite=moneda sonido=moneda valor=10
+//   objeto 2 = tipo=llave llave=plateada sprite=llave
+//   objeto 3 = tipo=cebolla sprite=cebolla vida=25
+//   objeto 4 = tipo=velocidad sprite=pocion factor=1.6 duracion=5
+//   enemigo = sprite=guardia ruta=5,9;12,9 velocidad=1.5 vision=6 dano=25 sonido=golpe
+//   peligro 1 = dano=10 sonido=chapoteo
+//   comandos_terminal = si
+//
+//   suelo 1 = pasto
+//   techo 1 = piedra
+//   altura 1 = 0.4
+//   cielo = cielo
+//   niebla = color=40,50,40 inicio=3 fin=10
+//   luz_ambiente = 0.4
+//   luz = pos=3.5,3.5 color=255,170,80 radio=4 parpadeo=0.3
+//   luz = celda=3 color=255,150,200 radio=3 intensidad=1.5
+//
+//   [mapa]
+//   1111111111111111
+//   1200000100000001
+//   ...
+//
+//   [paredes]    (opcional, mismas dimensiones que el mapa)
+//   [suelo]      (opcional, mismas dimensiones que el mapa)
+//   [techo]      (opcional, mismas dimensiones que el mapa)
+//   [alturas]    (opcional, mismas dimensiones que el mapa)
+//   [objetos]    (opcional, mismas dimensiones que el mapa)
+//   [peligros]   (opcional, mismas dimensiones que el mapa)
+//
+// Cada carácter del mapa es el código de la celda (0..9):
+// 0 = vacío, 1 = pared, 3 = salida; los códigos declarados con `amigo <código>` son coleccionables
+// y los declarados con `puerta <código>` son puertas corredizas (se abren con la tecla de usar);
+// cualquier otro código es un error.
+// En `[paredes]` cada dígito es un id de `pared <id>` (textura de todas las caras, o `textura=` y
+// reemplazos por cara con `norte=`, `sur=`, `este=`, `oeste=`); 0 = la de `pared = ...`.
+// Con `opacidad=` (o texturas con huecos transparentes) la pared deja ver lo que hay detrás.
+// Toda pared (y la salida, que es pared mientras está cerrada) tiene que tener textura.
+// En `[suelo]` y `[techo]` cada dígito es un id de textura declarado con `suelo <id>` / `techo <id>`;
+// 0 = sin textura (color plano, o el cielo panorámico en el techo si hay `cielo`).
+// En `[alturas]` cada dígito es un id declarado con `altura <id> = <valor>` (en unidades de pared,
+// 1 = altura normal); 0 = altura normal. Sobre las paredes bajas se ve lo que hay detrás.
+// Un sprite con `angulos=n` es un atlas de n columnas (vistas: la 0 de frente y las siguientes
+// girando hacia la derecha del personaje) y `quieto + camina` filas (cuadros de animación);
+// `mira=x,y` es hacia dónde mira y `opacidad=` lo vuelve translúcido.
+// En `[objetos]` cada dígito es un id de `objeto <id>`, en una celda vacía del mapa; 0 = nada.
+// Amigos y objetos se recogen al pasar a menos de `PICKUP_RADIUS` de su centro.
+// Cada `enemigo` recorre su ronda de celdas, persigue al jugador cuando lo ve y le quita vida
+// al alcanzarlo (ver `enemigos.rs`).
+// En `[peligros]` cada dígito es un id de `peligro <id>` (agua de pantano, fuego) que le quita vida
+// al jugador mientras esté sobre esa celda; 0 = nada. Tras cada golpe el jugador es invulnerable
+// durante `INVULNERABLE_SECS`.
+// El jugador choca con las paredes como un círculo de radio `radio_jugador` (en celdas, menor que
+// 0.5 para que quepa por los pasillos; por defecto `PLAYER_RADIUS`).
+// `fantasma` es el sprite con el que se ve la mejor partida anterior del nivel al correr contra
+// ella (ver `repeticion::Ghost`); sin `opacidad=` es translúcido a `GHOST_OPACITY`.
+
+pub const CELL_EMPTY: i32 = 0;
+pub const CELL_WALL: i32 = 1;
+pub const CELL_EXIT: i32 = 3;
+
+/// Distancia (en celdas) a la que el jugador recoge amigos y objetos.
+pub const PICKUP_RADIUS: f32 = 0.5;
+/// Vida máxima del jugador (y con la que empieza).
+pub const MAX_HEALTH: i32 = 100;
+/// Segundos en los que el jugador no recibe daño después de un golpe.
+pub const INVULNERABLE_SECS: f32 = 1.0;
+/// Radio (en celdas) con el que el jugador choca contra las paredes si el nivel no lo indica.
+pub const PLAYER_RADIUS: f32 = 0.2;
+/// Opacidad del sprite del fantasma si el nivel no la indica.
+pub const GHOST_OPACITY: f32 = 0.5;
+/// Tiempo par más largo que acepta un nivel (un día), para que el bono de tiempo no desborde.
+pub const MAX_PAR_SECS: f32 = 86_400.0;
+
+/// Velocidad de apertura/cierre de las puertas (fracción de recorrido por segundo).
+pub const DOOR_SPEED: f32 = 1.5;
+/// Apertura a partir de la cual se puede atravesar una puerta.
+pub const DOOR_PASSABLE: f32 = 0.9;
+
+/// Error de sintaxis o validación en un archivo de nivel, con su posición (1-based).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self { line, column, message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "línea {}, columna {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error al cargar un nivel desde disco (lectura o parseo).
+#[derive(Debug)]
+pub enum LevelError {
+    Io(String, std::io::Error),
+    Parse(String, ParseError),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(path, e) => write!(f, "no se pudo leer {}: {}", path, e),
+            LevelError::Parse(path, e) => write!(f, "{}: {}", path, e),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+/// Cuadrícula de celdas de tamaño arbitrario (fila a fila).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<i32>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, cells: Vec<i32>) -> Self {
+        assert_eq!(cells.len(), width * height, "tamaño de cuadrícula inconsistente");
+        Self { width, height, cells }
+    }
+
+    /// Celda en (x, y), o `None` si está fuera del mapa.
+    pub fn get(&self, x: i32, y: i32) -> Option<i32> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, val: i32) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = val;
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    /// Encuentra la primera celda con el valor `val` (retorna coords como Vec2 del centro de la celda)
+    pub fn find_first(&self, val: i32) -> Option<Vec2> {
+        self.cells.iter().position(|&c| c == val).map(|i| {
+            vec2((i % self.width) as f32 + 0.5, (i / self.width) as f32 + 0.5)
+        })
+    }
+
+    /// Cuenta cuántas celdas cumplen `pred`.
+    pub fn count(&self, pred: impl Fn(i32) -> bool) -> usize {
+        self.cells.iter().filter(|&&c| pred(c)).count()
+    }
+}
+
+/// Apariencia de un sprite del nivel: textura, escala respecto a una pared y bob vertical.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteDef {
+    /// Nombre de la textura (clave de `LevelDef::textures`).
+    pub texture: String,
+    pub scale: f32,
+    /// Velocidad (rad/s) y amplitud (fracción del alto) del movimiento vertical.
+    pub bob_speed: f32,
+    pub bob_amount: f32,
+    /// Vistas del personaje por vuelta completa (columnas del atlas; 1 = se ve igual de todos lados).
+    pub angles: usize,
+    /// Cuadros (filas del atlas) de la animación quieta y, a continuación, de la de caminar.
+    pub idle_frames: usize,
+    pub walk_frames: usize,
+    pub fps: f32,
+    /// Hacia dónde mira el personaje (normalizado); elige la vista según desde dónde se lo mira.
+    pub facing: Vec2,
+    /// 1 = opaco; con menos se ve lo que hay detrás (amigos fantasma).
+    pub opacity: f32,
+}
+
+/// Tipo de coleccionable (amigo) asociado a un código de celda.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectibleDef {
+    pub cell: i32,
+    pub sprite: SpriteDef,
+    /// Nombre del sonido que suena al recogerlo (clave de `LevelDef::sounds`).
+    pub sound: Option<String>,
+    /// Llave que se obtiene al recogerlo.
+    pub key: Option<String>,
+}
+
+/// Enemigo declarado con `enemigo = ...`: aparece en el primer punto de su ronda y la recorre
+/// en orden (volviendo al primero) hasta que ve al jugador.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyDef {
+    pub sprite: SpriteDef,
+    /// Celdas de la ronda.
+    pub route: Vec<(i32, i32)>,
+    /// Celdas por segundo.
+    pub speed: f32,
+    /// Distancia (en celdas) hasta la que ve al jugador.
+    pub vision: f32,
+    /// Vida que le quita al jugador al alcanzarlo (`MAX_HEALTH` lo atrapa de una).
+    pub damage: i32,
+    /// Nombre del sonido que suena al alcanzar al jugador (clave de `LevelDef::sounds`).
+    pub sound: Option<String>,
+}
+
+/// Celda peligrosa declarada con `peligro <id> = ...`; se coloca con su id en `[peligros]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HazardDef {
+    pub id: i32,
+    /// Vida que quita cada golpe (uno por `INVULNERABLE_SECS` mientras se siga encima).
+    pub damage: i32,
+    /// Nombre del sonido que suena al lastimarse (clave de `LevelDef::sounds`).
+    pub sound: Option<String>,
+}
+
+/// Efecto de un objeto al recogerlo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemEffect {
+    /// Suma puntos (monedas).
+    Score(u32),
+    /// Da una llave para las puertas con `llave=<nombre>`.
+    Key(String),
+    /// Devuelve vida, sin pasar de `MAX_HEALTH` (cebollas).
+    Health(i32),
+    /// Multiplica la velocidad del jugador durante `secs` segundos (pociones).
+    Speed { factor: f32, secs: f32 },
+}
+
+/// Tipo de objeto declarado con `objeto <id> = tipo=...`; se coloca con su id en `[objetos]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemDef {
+    pub id: i32,
+    pub effect: ItemEffect,
+    pub sprite: SpriteDef,
+    /// Nombre del sonido que suena al recogerlo (clave de `LevelDef::sounds`).
+    pub sound: Option<String>,
+}
+
+/// Texturas de las cuatro caras de un tipo de pared. El norte es la cara que da a `y - 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WallFaces {
+    pub north: String,
+    pub south: String,
+    pub east: String,
+    pub west: String,
+    /// 1 = opaca; con menos se ve lo que hay detrás (vidrios). Las texturas con huecos
+    /// transparentes (rejas, setos) dejan ver a través aunque la pared sea opaca.
+    pub opacity: f32,
+}
+
+/// Textura animada: la imagen declarada con `textura` es un atlas de `frames` cuadros del mismo
+/// tamaño, en filas de `columns`, que se reproducen a `fps` cuadros por segundo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationDef {
+    pub frames: usize,
+    pub columns: usize,
+    pub fps: f32,
+}
+
+/// Evento del nivel que destraba una puerta.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoorEvent {
+    /// Se encontraron todos los amigos requeridos (la salida está abierta).
+    FriendsFound,
+}
+
+/// Tipo de puerta asociado a un código de celda.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoorDef {
+    pub cell: i32,
+    pub texture: String,
+    /// Llave necesaria para abrirla.
+    pub key: Option<String>,
+    /// Evento necesario para abrirla.
+    pub event: Option<DoorEvent>,
+}
+
+/// Niebla por distancia: a partir de `start` el color se mezcla con `color` hasta cubrirlo
+/// por completo en `end` (distancias en celdas).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FogDef {
+    pub color: (u8, u8, u8),
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Dónde está una luz: en un punto fijo o sobre cada celda con cierto código
+/// (amigos, salida), en cuyo caso se apaga cuando la celda deja de tener ese código.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightSource {
+    Pos(Vec2),
+    Cell(i32),
+}
+
+/// Luz puntual declarada con `luz = ...`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightDef {
+    pub source: LightSource,
+    pub color: (u8, u8, u8),
+    /// Distancia (en celdas) a la que la luz deja de aportar.
+    pub radius: f32,
+    pub intensity: f32,
+    /// Fracción (0..1) en la que la intensidad puede bajar al parpadear.
+    pub flicker: f32,
+}
+
+/// Definición de un nivel tal como viene del archivo de datos.
+#[derive(Debug, Clone)]
+pub struct LevelDef {
+    pub name: String,
+    pub grid: Grid,
+    pub spawn: Vec2,
+    pub dir: Vec2,
+    pub music: Option<String>,
+    /// Texturas por nombre lógico (planicie, bosque, castillo, burro, ...).
+    pub textures: HashMap<String, String>,
+    /// Texturas que son atlas de animación, por nombre de textura.
+    pub animations: HashMap<String, AnimationDef>,
+    /// Sonidos por nombre lógico (moneda, final, ...).
+    pub sounds: HashMap<String, String>,
+    /// Amigos que hay que encontrar para abrir la salida (`None` = todos los del mapa).
+    pub required_friends: Option<usize>,
+    /// Tiempo de referencia (segundos) para el bono de tiempo del puntaje.
+    pub par_time: Option<f32>,
+    /// Radio del jugador para chocar con las paredes (`radio_jugador`).
+    pub player_radius: f32,
+    pub collectibles: Vec<CollectibleDef>,
+    /// Tipos de objeto por id y en qué celda hay uno (`None` si el nivel no tiene `[objetos]`).
+    pub items: Vec<ItemDef>,
+    pub item_grid: Option<Grid>,
+    pub enemies: Vec<EnemyDef>,
+    /// Tipos de peligro por id y en qué celda hay uno (`None` si el nivel no tiene `[peligros]`).
+    pub hazards: Vec<HazardDef>,
+    pub hazard_grid: Option<Grid>,
+    pub doors: Vec<DoorDef>,
+    /// Sprite que aparece en la salida cuando se abre.
+    pub exit_sprite: Option<SpriteDef>,
+    /// Sprite del fantasma de la mejor partida (`None`: el fantasma solo se ve en el minimapa).
+    pub ghost_sprite: Option<SpriteDef>,
+    /// Si se escucha stdin para volver al menú escribiendo `menu`.
+    pub terminal_commands: bool,
+    /// Id de tipo de pared por celda (`None` si el nivel no tiene `[paredes]`).
+    pub walls: Option<Grid>,
+    /// Texturas por id de pared; el id 0 es el tipo por defecto (`pared = ...`).
+    pub wall_textures: HashMap<i32, WallFaces>,
+    /// Ids de textura de suelo/techo por celda (`None` si el nivel no tiene la sección).
+    pub floor: Option<Grid>,
+    pub ceiling: Option<Grid>,
+    /// Nombre de textura para cada id de suelo/techo.
+    pub floor_textures: HashMap<i32, String>,
+    pub ceiling_textures: HashMap<i32, String>,
+    /// Textura panorámica que se ve donde no hay techo.
+    pub sky: Option<String>,
+    /// Ids de altura por celda y el valor de cada id.
+    pub heights: Option<Grid>,
+    pub height_values: HashMap<i32, f32>,
+    pub fog: Option<FogDef>,
+    /// Luz base de todas las celdas (1 = iluminación uniforme de siempre).
+    pub ambient: f32,
+    pub lights: Vec<LightDef>,
+}
+
+impl LevelDef {
+    /// Tipo de coleccionable para un código de celda, si lo es.
+    pub fn collectible(&self, cell: i32) -> Option<&CollectibleDef> {
+        self.collectibles.iter().find(|c| c.cell == cell)
+    }
+
+    /// Tipo de objeto para un id de `[objetos]`.
+    pub fn item(&self, id: i32) -> Option<&ItemDef> {
+        self.items.iter().find(|i| i.id == id)
+    }
+
+    /// Peligro en la celda de `pos`, si hay.
+    pub fn hazard_at(&self, pos: Vec2) -> Option<&HazardDef> {
+        let id = self.hazard_grid.as_ref()?.get(pos.x.floor() as i32, pos.y.floor() as i32)?;
+        self.hazards.iter().find(|h| h.id == id)
+    }
+
+    /// Si el jugador puede perder vida en este nivel (hay enemigos o peligros).
+    pub fn has_damage(&self) -> bool {
+        !self.enemies.is_empty() || !self.hazards.is_empty()
+    }
+
+    /// Sonido al recoger `kind`, si tiene.
+    pub fn pickup_sound(&self, kind: PickupKind) -> Option<&String> {
+        match kind {
+            PickupKind::Friend(cell) => self.collectible(cell)?.sound.as_ref(),
+            PickupKind::Item(id) => self.item(id)?.sound.as_ref(),
+        }
+    }
+
+    /// Texturas de la pared en `(x, y)`: las de su id en `[paredes]` o las por defecto.
+    pub fn wall_faces(&self, x: i32, y: i32) -> Option<&WallFaces> {
+        let id = self.walls.as_ref().and_then(|w| w.get(x, y)).unwrap_or(0);
+        self.wall_textures.get(&id).or_else(|| self.wall_textures.get(&0))
+    }
+
+    /// Altura de la pared en `(x, y)` (1 si el nivel no define `[alturas]`).
+    pub fn wall_height(&self, x: i32, y: i32) -> f32 {
+        let id = self.heights.as_ref().and_then(|h| h.get(x, y)).unwrap_or(0);
+        self.height_values.get(&id).copied().unwrap_or(1.0)
+    }
+
+    /// Tipo de puerta para un código de celda, si lo es.
+    pub fn door(&self, cell: i32) -> Option<&DoorDef> {
+        self.doors.iter().find(|d| d.cell == cell)
+    }
+
+    /// Lee y parsea un archivo de nivel.
+    pub fn load(path: &str) -> Result<Self, LevelError> {
+        let src = std::fs::read_to_string(path).map_err(|e| LevelError::Io(path.to_string(), e))?;
+        Self::parse(&src).map_err(|e| LevelError::Parse(path.to_string(), e))
+    }
+
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut name = None;
+        let mut spawn = None;
+        let mut dir = vec2(1.0, 0.0);
+        let mut music = None;
+        let mut textures = HashMap::new();
+        let mut sounds = HashMap::new();
+        let mut required_friends = None;
+        let mut par_time = None;
+        let mut player_radius = PLAYER_RADIUS;
+        let mut collectibles: Vec<CollectibleDef> = Vec::new();
+        let mut items: Vec<ItemDef> = Vec::new();
+        // enemigos y dónde se declararon (para señalar rondas inválidas)
+        let mut enemies: Vec<(EnemyDef, usize, usize)> = Vec::new();
+        let mut hazards: Vec<HazardDef> = Vec::new();
+        let mut doors: Vec<DoorDef> = Vec::new();
+        // llaves pedidas por puertas, para comprobar que algún amigo u objeto las da (nombre, línea, columna)
+        let mut key_refs: Vec<(String, usize, usize)> = Vec::new();
+        let mut exit_sprite = None;
+        let mut ghost_sprite = None;
+        let mut terminal_commands = false;
+        let mut floor_textures = HashMap::new();
+        let mut ceiling_textures = HashMap::new();
+        let mut sky = None;
+        let mut height_values = HashMap::new();
+        let mut wall_textures = HashMap::new();
+        let mut animations = HashMap::new();
+        let mut fog = None;
+        let mut ambient = 1.0;
+        let mut lights = Vec::new();
+        // referencias a texturas/sonidos que se validan al final (nombre, línea, columna)
+        let mut texture_refs: Vec<(String, usize, usize)> = Vec::new();
+        let mut sound_refs: Vec<(String, usize, usize)> = Vec::new();
+
+        // secciones de cuadrícula ([mapa], [paredes], [suelo], [techo], [alturas], [objetos], [peligros]) en orden de aparición
+        let mut sections: Vec<Section> = Vec::new();
+        let mut last_line = 0;
+
+        for (i, raw) in src.lines().enumerate() {
+            let line_no = i + 1;
+            last_line = line_no;
+            let line = raw.trim_end();
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = trimmed.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+                if !matches!(name, "mapa" | "paredes" | "suelo" | "techo" | "alturas" | "objetos" | "peligros") {
+                    return Err(ParseError::new(line_no, 1, format!("sección desconocida `[{}]`", name)));
+                }
+                if sections.iter().any(|s| s.name == name) {
+                    return Err(ParseError::new(line_no, 1, format!("la sección `[{}]` está repetida", name)));
+                }
+                sections.push(Section { name: name.to_string(), header_line: line_no, rows: Vec::new() });
+                continue;
+            }
+
+            if let Some(section) = sections.last_mut() {
+                section.rows.push((line_no, parse_row(line, line_no)?));
+                continue;
+            }
+
+            let indent = line.len() - trimmed.len();
+            let Some(eq) = trimmed.find('=') else {
+                return Err(ParseError::new(line_no, indent + 1, "se esperaba `clave = valor` o `[mapa]`"));
+            };
+            let mut key_parts = trimmed[..eq].split_whitespace();
+            let key = key_parts.next().unwrap_or("");
+            let arg = key_parts.next();
+            if key_parts.next().is_some() {
+                return Err(ParseError::new(line_no, indent + 1, format!("clave inválida `{}`", trimmed[..eq].trim())));
+            }
+            let value = trimmed[eq + 1..].trim();
+            let value_col = indent + eq + 2 + (trimmed[eq + 1..].len() - trimmed[eq + 1..].trim_start().len());
+
+            if value.is_empty() {
+                return Err(ParseError::new(line_no, value_col, format!("falta el valor de `{}`", key)));
+            }
+
+            match (key, arg) {
+                ("nombre", None) => name = Some(value.to_string()),
+                ("spawn", None) => spawn = Some((parse_vec2(value, line_no, value_col)?, line_no, value_col)),
+                ("direccion", None) => {
+                    let d = parse_vec2(value, line_no, value_col)?;
+                    if d.length_squared() == 0.0 {
+                        return Err(ParseError::new(line_no, value_col, "la dirección no puede ser (0, 0)"));
+                    }
+                    dir = d.normalize();
+                }
+                ("musica", None) => music = Some(value.to_string()),
+                ("textura", Some(n)) => {
+                    textures.insert(n.to_string(), value.to_string());
+                }
+                ("animacion", Some(n)) => {
+                    texture_refs.push((n.to_string(), line_no, indent + 1));
+                    animations.insert(n.to_string(), parse_animation(value, line_no, value_col)?);
+                }
+                ("sonido", Some(n)) => {
+                    sounds.insert(n.to_string(), value.to_string());
+                }
+                ("amigos_requeridos", None) => {
+                    let n = value.parse::<usize>().map_err(|_| {
+                        ParseError::new(line_no, value_col, format!("número inválido `{}`", value))
+                    })?;
+                    required_friends = Some(n);
+                }
+                ("tiempo_par", None) => {
+                    let t = parse_f32(value, line_no, value_col)?;
+                    if !(t > 0.0 && t <= MAX_PAR_SECS) {
+                        return Err(ParseError::new(
+                            line_no,
+                            value_col,
+                            format!("el tiempo par tiene que estar entre 0 y {} segundos", MAX_PAR_SECS),
+                        ));
+                    }
+                    par_time = Some(t);
+                }
+                ("radio_jugador", None) => {
+                    let r = parse_f32(value, line_no, value_col)?;
+                    if r <= 0.0 || r >= 0.5 {
+                        return Err(ParseError::new(line_no, value_col, "el radio del jugador tiene que estar entre 0 y 0.5"));
+                    }
+                    player_radius = r;
+                }
+                ("amigo", Some(code)) => {
+                    let cell = match code.parse::<i32>() {
+                        Ok(c) if (0..=9).contains(&c) && c != CELL_EMPTY && c != CELL_WALL && c != CELL_EXIT => c,
+                        _ => {
+                            return Err(ParseError::new(
+                                line_no,
+                                indent + 1,
+                                format!("código de amigo inválido `{}` (0, 1 y 3 están reservados)", code),
+                            ));
+                        }
+                    };
+                    if collectibles.iter().any(|c| c.cell == cell) || doors.iter().any(|d| d.cell == cell) {
+                        return Err(ParseError::new(line_no, indent + 1, format!("el código {} ya está declarado", cell)));
+                    }
+                    let fields = parse_fields(value, line_no, value_col)?;
+                    let sprite = parse_sprite(&fields, 0.45, 2.0, 0.06, line_no, value_col, &mut texture_refs)?;
+                    let mut sound = None;
+                    let mut key = None;
+                    for f in &fields {
+                        match f.key {
+                            "sonido" => {
+                                sound_refs.push((f.value.to_string(), line_no, f.column));
+                                sound = Some(f.value.to_string());
+                            }
+                            "llave" => key = Some(f.value.to_string()),
+                            k if SPRITE_FIELDS.contains(&k) => {}
+                            other => {
+                                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+                            }
+                        }
+                    }
+                    collectibles.push(CollectibleDef { cell, sprite, sound, key });
+                }
+                ("objeto", Some(id)) => {
+                    let id = match id.parse::<i32>() {
+                        Ok(n) if (1..=9).contains(&n) => n,
+                        _ => {
+                            return Err(ParseError::new(line_no, indent + 1, format!("id de objeto inválido `{}` (1-9)", id)));
+                        }
+                    };
+                    if items.iter().any(|i| i.id == id) {
+                        return Err(ParseError::new(line_no, indent + 1, format!("el objeto {} ya está declarado", id)));
+                    }
+                    let fields = parse_fields(value, line_no, value_col)?;
+                    let sprite = parse_sprite(&fields, 0.3, 3.0, 0.05, line_no, value_col, &mut texture_refs)?;
+                    let (effect, sound) = parse_item(&fields, line_no, value_col, &mut sound_refs)?;
+                    items.push(ItemDef { id, effect, sprite, sound });
+                }
+                ("enemigo", None) => {
+                    let fields = parse_fields(value, line_no, value_col)?;
+                    let sprite = parse_sprite(&fields, 0.8, 0.0, 0.0, line_no, value_col, &mut texture_refs)?;
+                    let mut enemy = EnemyDef { sprite, route: Vec::new(), speed: 1.5, vision: 6.0, damage: 25, sound: None };
+                    for f in &fields {
+                        let field_col = f.column + f.key.len() + 1;
+                        let positive = || match parse_f32(f.value, line_no, field_col) {
+                            Ok(v) if v > 0.0 => Ok(v),
+                            _ => Err(ParseError::new(line_no, field_col, format!("se esperaba un número positivo, se encontró `{}`", f.value))),
+                        };
+                        match f.key {
+                            "ruta" => enemy.route = parse_route(f.value, line_no, field_col)?,
+                            "velocidad" => enemy.speed = positive()?,
+                            "vision" => enemy.vision = positive()?,
+                            "dano" => match f.value.parse::<i32>() {
+                                Ok(n) if n > 0 => enemy.damage = n,
+                                _ => {
+                                    return Err(ParseError::new(line_no, field_col, format!("daño inválido `{}`", f.value)));
+                                }
+                            },
+                            "sonido" => {
+                                sound_refs.push((f.value.to_string(), line_no, f.column));
+                                enemy.sound = Some(f.value.to_string());
+                            }
+                            k if SPRITE_FIELDS.contains(&k) => {}
+                            other => {
+                                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+                            }
+                        }
+                    }
+                    if enemy.route.is_empty() {
+                        return Err(ParseError::new(line_no, value_col, "falta el campo `ruta` (celdas `x,y;x,y;...`)"));
+                    }
+                    enemies.push((enemy, line_no, value_col));
+                }
+                ("peligro", Some(id)) => {
+                    let id = match id.parse::<i32>() {
+                        Ok(n) if (1..=9).contains(&n) => n,
+                        _ => {
+                            return Err(ParseError::new(line_no, indent + 1, format!("id de peligro inválido `{}` (1-9)", id)));
+                        }
+                    };
+                    if hazards.iter().any(|h| h.id == id) {
+                        return Err(ParseError::new(line_no, indent + 1, format!("el peligro {} ya está declarado", id)));
+                    }
+                    let mut hazard = HazardDef { id, damage: 0, sound: None };
+                    for f in parse_fields(value, line_no, value_col)? {
+                        let field_col = f.column + f.key.len() + 1;
+                        match f.key {
+                            "dano" => match f.value.parse::<i32>() {
+                                Ok(n) if n > 0 => hazard.damage = n,
+                                _ => {
+                                    return Err(ParseError::new(line_no, field_col, format!("daño inválido `{}`", f.value)));
+                                }
+                            },
+                            "sonido" => {
+                                sound_refs.push((f.value.to_string(), line_no, f.column));
+                                hazard.sound = Some(f.value.to_string());
+                            }
+                            other => {
+                                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+                            }
+                        }
+                    }
+                    if hazard.damage == 0 {
+                        return Err(ParseError::new(line_no, value_col, "falta el campo `dano`"));
+                    }
+                    hazards.push(hazard);
+                }
+                ("puerta", Some(code)) => {
+                    let cell = match code.parse::<i32>() {
+                        Ok(c) if (0..=9).contains(&c) && c != CELL_EMPTY && c != CELL_WALL && c != CELL_EXIT => c,
+                        _ => {
+                            return Err(ParseError::new(
+                                line_no,
+                                indent + 1,
+                                format!("código de puerta inválido `{}` (0, 1 y 3 están reservados)", code),
+                            ));
+                        }
+                    };
+                    if collectibles.iter().any(|c| c.cell == cell) || doors.iter().any(|d| d.cell == cell) {
+                        return Err(ParseError::new(line_no, indent + 1, format!("el código {} ya está declarado", cell)));
+                    }
+                    let mut door = DoorDef { cell, texture: String::new(), key: None, event: None };
+                    for f in parse_fields(value, line_no, value_col)? {
+                        let field_col = f.column + f.key.len() + 1;
+                        match f.key {
+                            "textura" => {
+                                texture_refs.push((f.value.to_string(), line_no, field_col));
+                                door.texture = f.value.to_string();
+                            }
+                            "llave" => {
+                                key_refs.push((f.value.to_string(), line_no, field_col));
+                                door.key = Some(f.value.to_string());
+                            }
+                            "evento" => match f.value {
+                                "amigos" => door.event = Some(DoorEvent::FriendsFound),
+                                other => {
+                                    return Err(ParseError::new(line_no, field_col, format!("evento desconocido `{}`", other)));
+                                }
+                            },
+                            other => {
+                                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+                            }
+                        }
+                    }
+                    if door.texture.is_empty() {
+                        return Err(ParseError::new(line_no, value_col, "falta el campo `textura`"));
+                    }
+                    doors.push(door);
+                }
+                ("salida", None) => {
+                    let fields = parse_fields(value, line_no, value_col)?;
+                    if let Some(f) = fields.iter().find(|f| !SPRITE_FIELDS.contains(&f.key)) {
+                        return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", f.key)));
+                    }
+                    exit_sprite = Some(parse_sprite(&fields, 0.85, 2.4, 0.08, line_no, value_col, &mut texture_refs)?);
+                }
+                ("fantasma", None) => {
+                    let fields = parse_fields(value, line_no, value_col)?;
+                    if let Some(f) = fields.iter().find(|f| !SPRITE_FIELDS.contains(&f.key)) {
+                        return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", f.key)));
+                    }
+                    let mut sprite = parse_sprite(&fields, 0.8, 0.0, 0.0, line_no, value_col, &mut texture_refs)?;
+                    if !fields.iter().any(|f| f.key == "opacidad") {
+                        sprite.opacity = GHOST_OPACITY;
+                    }
+                    ghost_sprite = Some(sprite);
+                }
+                ("comandos_terminal", None) => {
+                    terminal_commands = parse_bool(value, line_no, value_col)?;
+                }
+                ("suelo", Some(id)) | ("techo", Some(id)) => {
+                    let id = match id.parse::<i32>() {
+                        Ok(n) if (1..=9).contains(&n) => n,
+                        _ => {
+                            return Err(ParseError::new(line_no, indent + 1, format!("id de textura inválido `{}` (1-9)", id)));
+                        }
+                    };
+                    texture_refs.push((value.to_string(), line_no, value_col));
+                    let table = if key == "suelo" { &mut floor_textures } else { &mut ceiling_textures };
+                    table.insert(id, value.to_string());
+                }
+                ("pared", id) => {
+                    let id = match id.map(|id| id.parse::<i32>()) {
+                        None => 0,
+                        Some(Ok(n)) if (1..=9).contains(&n) => n,
+                        Some(_) => {
+                            return Err(ParseError::new(line_no, indent + 1, format!("id de pared inválido `{}` (1-9)", id.unwrap_or(""))));
+                        }
+                    };
+                    let faces = parse_wall_faces(value, line_no, value_col, &mut texture_refs)?;
+                    wall_textures.insert(id, faces);
+                }
+                ("altura", Some(id)) => {
+                    let id = match id.parse::<i32>() {
+                        Ok(n) if (1..=9).contains(&n) => n,
+                        _ => {
+                            return Err(ParseError::new(line_no, indent + 1, format!("id de altura inválido `{}` (1-9)", id)));
+                        }
+                    };
+                    let h = parse_f32(value, line_no, value_col)?;
+                    if h <= 0.0 {
+                        return Err(ParseError::new(line_no, value_col, "la altura tiene que ser mayor que 0"));
+                    }
+                    height_values.insert(id, h);
+                }
+                ("cielo", None) => {
+                    texture_refs.push((value.to_string(), line_no, value_col));
+                    sky = Some(value.to_string());
+                }
+                ("niebla", None) => {
+                    fog = Some(parse_fog(value, line_no, value_col)?);
+                }
+                ("luz_ambiente", None) => {
+                    ambient = parse_f32(value, line_no, value_col)?;
+                    if ambient < 0.0 {
+                        return Err(ParseError::new(line_no, value_col, "la luz ambiente no puede ser negativa"));
+                    }
+                }
+                ("luz", None) => {
+                    lights.push(parse_light(value, line_no, value_col)?);
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        line_no,
+                        indent + 1,
+                        format!("clave desconocida `{}`", trimmed[..eq].trim()),
+                    ));
+                }
+            }
+        }
+
+        let Some(map_section) = sections.iter().find(|s| s.name == "mapa") else {
+            return Err(ParseError::new(last_line + 1, 1, "falta la sección `[mapa]`"));
+        };
+        let grid = map_section.to_grid(None)?;
+        // cada código del mapa es vacío, pared o salida, o está declarado como amigo o puerta
+        let declared = |c: i32| {
+            matches!(c, CELL_EMPTY | CELL_WALL | CELL_EXIT) || collectibles.iter().any(|a| a.cell == c) || doors.iter().any(|d| d.cell == c)
+        };
+        for (line_no, row) in &map_section.rows {
+            if let Some(col) = row.iter().position(|&c| !declared(c)) {
+                let c = row[col];
+                return Err(ParseError::new(
+                    *line_no,
+                    col + 1,
+                    format!("código {} no declarado con `amigo {} = ...` ni `puerta {} = ...`", c, c, c),
+                ));
+            }
+        }
+
+        let mut walls = None;
+        let mut floor = None;
+        let mut ceiling = None;
+        let mut heights = None;
+        let mut item_grid = None;
+        let mut hazard_grid = None;
+        for section in &sections {
+            // capa destino, ids declarados y la clave con la que se declaran
+            let (layer, declared, key): (_, HashSet<i32>, _) = match section.name.as_str() {
+                "paredes" => (&mut walls, wall_textures.keys().copied().collect(), "pared"),
+                "suelo" => (&mut floor, floor_textures.keys().copied().collect(), "suelo"),
+                "techo" => (&mut ceiling, ceiling_textures.keys().copied().collect(), "techo"),
+                "alturas" => (&mut heights, height_values.keys().copied().collect(), "altura"),
+                "objetos" => (&mut item_grid, items.iter().map(|i| i.id).collect(), "objeto"),
+                "peligros" => (&mut hazard_grid, hazards.iter().map(|h| h.id).collect(), "peligro"),
+                _ => continue,
+            };
+            let ids = section.to_grid(Some((grid.width, grid.height)))?;
+            // cada id usado en la capa tiene que estar declarado
+            for (line_no, row) in &section.rows {
+                if let Some(col) = row.iter().position(|&id| id != 0 && !declared.contains(&id)) {
+                    return Err(ParseError::new(
+                        *line_no,
+                        col + 1,
+                        format!("id {} no declarado con `{} {} = ...`", row[col], key, row[col]),
+                    ));
+                }
+            }
+            *layer = Some(ids);
+        }
+
+        // los objetos van en celdas por las que se camina
+        if let Some(section) = sections.iter().find(|s| s.name == "objetos") {
+            for (y, (line_no, row)) in section.rows.iter().enumerate() {
+                if let Some(x) = row.iter().enumerate().position(|(x, &id)| id != 0 && grid.get(x as i32, y as i32) != Some(CELL_EMPTY)) {
+                    return Err(ParseError::new(*line_no, x + 1, "objeto sobre una celda que no está vacía"));
+                }
+            }
+        }
+
+        // los peligros, en celdas por las que se puede pasar (ni paredes, ni la salida, ni puertas)
+        if let Some(section) = sections.iter().find(|s| s.name == "peligros") {
+            for (y, (line_no, row)) in section.rows.iter().enumerate() {
+                let solid = |x: usize| match grid.get(x as i32, y as i32) {
+                    Some(c) => c == CELL_WALL || c == CELL_EXIT || doors.iter().any(|d| d.cell == c),
+                    None => true,
+                };
+                if let Some(x) = row.iter().enumerate().position(|(x, &id)| id != 0 && solid(x)) {
+                    return Err(ParseError::new(*line_no, x + 1, "peligro sobre una pared, puerta o la salida"));
+                }
+            }
+        }
+
+        // toda celda que se dibuja como pared tiene que tener textura
+        for (y, (line_no, row)) in map_section.rows.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell != CELL_WALL && cell != CELL_EXIT {
+                    continue;
+                }
+                let id = walls.as_ref().and_then(|w: &Grid| w.get(x as i32, y as i32)).unwrap_or(0);
+                if !wall_textures.contains_key(&id) && !wall_textures.contains_key(&0) {
+                    return Err(ParseError::new(
+                        *line_no,
+                        x + 1,
+                        "pared sin textura (declara `pared = <textura>` o asígnale un id en `[paredes]`)",
+                    ));
+                }
+            }
+        }
+
+        // las rondas pasan por celdas transitables (ni paredes, ni la salida, ni puertas)
+        for (enemy, line, col) in &enemies {
+            for &(x, y) in &enemy.route {
+                match grid.get(x, y) {
+                    Some(c) if c != CELL_WALL && c != CELL_EXIT && !doors.iter().any(|d| d.cell == c) => {}
+                    _ => {
+                        return Err(ParseError::new(*line, *col, format!("la ruta pasa por ({}, {}), que no es una celda libre", x, y)));
+                    }
+                }
+            }
+        }
+
+        for (name, line, col) in &texture_refs {
+            if !textures.contains_key(name) {
+                return Err(ParseError::new(*line, *col, format!("textura no declarada `{}`", name)));
+            }
+        }
+        for (name, line, col) in &key_refs {
+            let from_item = items.iter().any(|i| matches!(&i.effect, ItemEffect::Key(k) if k == name));
+            if !from_item && !collectibles.iter().any(|c| c.key.as_ref() == Some(name)) {
+                return Err(ParseError::new(*line, *col, format!("ningún amigo ni objeto da la llave `{}`", name)));
+            }
+        }
+        for (name, line, col) in &sound_refs {
+            if !sounds.contains_key(name) {
+                return Err(ParseError::new(*line, *col, format!("sonido no declarado `{}`", name)));
+            }
+        }
+
+        let Some((spawn, spawn_line, spawn_col)) = spawn else {
+            return Err(ParseError::new(last_line + 1, 1, "falta la clave obligatoria `spawn`"));
+        };
+        match grid.get(spawn.x.floor() as i32, spawn.y.floor() as i32) {
+            Some(0) => {}
+            Some(_) => {
+                return Err(ParseError::new(spawn_line, spawn_col, "el spawn cae sobre una celda no vacía"));
+            }
+            None => {
+                return Err(ParseError::new(spawn_line, spawn_col, "el spawn está fuera del mapa"));
+            }
+        }
+
+        Ok(Self {
+            name: name.unwrap_or_else(|| "Sin nombre".to_string()),
+            grid,
+            spawn,
+            dir,
+            music,
+            textures,
+            animations,
+            sounds,
+            required_friends,
+            par_time,
+            player_radius,
+            collectibles,
+            items,
+            item_grid,
+            enemies: enemies.into_iter().map(|(e, _, _)| e).collect(),
+            hazards,
+            hazard_grid,
+            doors,
+            exit_sprite,
+            ghost_sprite,
+            terminal_commands,
+            walls,
+            wall_textures,
+            floor,
+            ceiling,
+            floor_textures,
+            ceiling_textures,
+            sky,
+            heights,
+            height_values,
+            fog,
+            ambient,
+            lights,
+        })
+    }
+}
+
+/// Orientación de la hoja de una puerta, deducida de las paredes vecinas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoorAxis {
+    /// Paralela al eje x (en `y + 0.5`): paredes a izquierda y derecha, se cruza moviéndose en y.
+    AlongX,
+    /// Paralela al eje y (en `x + 0.5`): se cruza moviéndose en x.
+    AlongY,
+}
+
+/// Estado de una puerta en la partida.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Door {
+    pub x: i32,
+    pub y: i32,
+    pub cell: i32,
+    pub axis: DoorAxis,
+    /// Cuánto se ha deslizado la hoja (0 = cerrada, 1 = abierta del todo).
+    pub open: f32,
+    /// Hacia dónde se está moviendo.
+    pub opening: bool,
+}
+
+/// Por qué una puerta no se abre.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DoorLock {
+    Key(String),
+    Event(DoorEvent),
+}
+
+/// Resultado de usar la tecla de acción frente a una puerta.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DoorUse {
+    /// No hay puerta al alcance (o el jugador está dentro y no se puede cerrar).
+    Nothing,
+    Toggled,
+    Locked(DoorLock),
+}
+
+/// Qué es algo que se puede recoger: un amigo (por código de celda) o un objeto (por id).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PickupKind {
+    Friend(i32),
+    Item(i32),
+}
+
+/// Amigo u objeto que sigue en el nivel, en el centro de su celda.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pickup {
+    pub pos: Vec2,
+    pub kind: PickupKind,
+}
+
+/// Aumento de velocidad en curso.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedBoost {
+    pub factor: f32,
+    /// Segundos que le quedan.
+    pub remaining: f32,
+}
+
+/// Estado de una partida en curso. Se crea clonando la plantilla `LevelDef` cada vez que se
+/// entra al nivel, así la definición nunca se modifica y volver a jugar empieza de cero.
+#[derive(Debug, Clone)]
+pub struct LevelState {
+    pub map: Grid,
+    /// Amigos que faltan por encontrar para abrir la salida.
+    pub coins: i32,
+    pub won: bool,
+    pub doors: Vec<Door>,
+    /// Llaves recogidas.
+    pub keys: HashSet<String>,
+    /// Amigos y objetos que todavía no se recogieron.
+    pub pickups: Vec<Pickup>,
+    pub score: u32,
+    pub health: i32,
+    pub speed_boost: Option<SpeedBoost>,
+    pub enemies: Vec<Enemy>,
+    /// La partida terminó porque el jugador se quedó sin vida.
+    pub lost: bool,
+    /// Segundos que le quedan al jugador sin recibir daño tras el último golpe.
+    pub invulnerable: f32,
+    /// Segundos jugados (sin contar las pausas).
+    pub elapsed: f32,
+    /// Estadísticas de la partida para el puntaje (ver `puntaje.rs`).
+    pub friends_found: u32,
+    pub items_collected: u32,
+    pub damage_taken: i32,
+}
+
+impl LevelState {
+    pub fn new(def: &LevelDef) -> Self {
+        let mut coins = def.grid.count(|c| def.collectible(c).is_some()) as i32;
+        if let Some(req) = def.required_friends {
+            coins = coins.min(req as i32);
+        }
+        let mut doors = Vec::new();
+        let mut pickups = Vec::new();
+        for y in 0..def.grid.height as i32 {
+            for x in 0..def.grid.width as i32 {
+                let center = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                if let Some(id) = def.item_grid.as_ref().and_then(|g| g.get(x, y)).filter(|&id| id != 0) {
+                    pickups.push(Pickup { pos: center, kind: PickupKind::Item(id) });
+                }
+                let Some(cell) = def.grid.get(x, y) else { continue };
+                if def.collectible(cell).is_some() {
+                    pickups.push(Pickup { pos: center, kind: PickupKind::Friend(cell) });
+                }
+                if def.door(cell).is_none() {
+                    continue;
+                }
+                let walled = |dx: i32, dy: i32| def.grid.get(x + dx, y + dy) == Some(CELL_WALL);
+                let axis = if walled(-1, 0) && walled(1, 0) { DoorAxis::AlongX } else { DoorAxis::AlongY };
+                doors.push(Door { x, y, cell, axis, open: 0.0, opening: false });
+            }
+        }
+        Self {
+            map: def.grid.clone(),
+            coins,
+            won: false,
+            doors,
+            keys: HashSet::new(),
+            pickups,
+            score: 0,
+            health: MAX_HEALTH,
+            speed_boost: None,
+            enemies: def.enemies.iter().enumerate().map(|(i, e)| Enemy::new(i, e)).collect(),
+            lost: false,
+            invulnerable: 0.0,
+            elapsed: 0.0,
+            friends_found: 0,
+            items_collected: 0,
+            damage_taken: 0,
+        }
+    }
+
+    pub fn door_at(&self, x: i32, y: i32) -> Option<&Door> {
+        self.doors.iter().find(|d| d.x == x && d.y == y)
+    }
+
+    /// Si la celda `(x, y)` no se puede atravesar: fuera del mapa, bloqueante o puerta sin abrir.
+    pub fn blocks_at(&self, x: i32, y: i32) -> bool {
+        let Some(cell) = self.map.get(x, y) else {
+            return true;
+        };
+        self.is_blocking(cell) || self.door_at(x, y).is_some_and(|d| d.open < DOOR_PASSABLE)
+    }
+
+    /// Qué falta para poder abrir `door`, si algo.
+    pub fn door_lock(&self, def: &LevelDef, door: &Door) -> Option<DoorLock> {
+        let dd = def.door(door.cell)?;
+        if let Some(key) = &dd.key
+            && !self.keys.contains(key)
+        {
+            return Some(DoorLock::Key(key.clone()));
+        }
+        match dd.event {
+            Some(DoorEvent::FriendsFound) if self.coins > 0 => Some(DoorLock::Event(DoorEvent::FriendsFound)),
+            _ => None,
+        }
+    }
+
+    /// Abre o cierra la puerta que está justo delante de `pos` mirando hacia `dir`
+    /// (o la celda en la que está parado, que solo se puede abrir).
+    pub fn use_door(&mut self, def: &LevelDef, pos: Vec2, dir: Vec2) -> DoorUse {
+        let here = (pos.x.floor() as i32, pos.y.floor() as i32);
+        let ahead = pos + dir.normalize_or_zero() * 0.9;
+        let ahead = (ahead.x.floor() as i32, ahead.y.floor() as i32);
+        let Some(i) = [ahead, here].iter().find_map(|&(x, y)| self.doors.iter().position(|d| d.x == x && d.y == y)) else {
+            return DoorUse::Nothing;
+        };
+        if let Some(lock) = self.door_lock(def, &self.doors[i]) {
+            return DoorUse::Locked(lock);
+        }
+        let door = &mut self.doors[i];
+        if door.opening && (door.x, door.y) == here {
+            // no cerrarla con el jugador adentro
+            return DoorUse::Nothing;
+        }
+        door.opening = !door.opening;
+        DoorUse::Toggled
+    }
+
+    /// Avanza la animación de las puertas `dt` segundos.
+    pub fn update_doors(&mut self, dt: f32) {
+        for door in &mut self.doors {
+            let step = DOOR_SPEED * dt;
+            door.open = if door.opening { (door.open + step).min(1.0) } else { (door.open - step).max(0.0) };
+        }
+    }
+
+    /// Si la celda bloquea el paso: paredes siempre, la salida (3) solo mientras falten amigos.
+    pub fn is_blocking(&self, cell: i32) -> bool {
+        cell == CELL_WALL || (cell == CELL_EXIT && self.coins > 0)
+    }
+
+    /// Recoge todo lo que esté a menos de `PICKUP_RADIUS` de `pos` y aplica su efecto; devuelve
+    /// lo recogido. Las cebollas se quedan en el suelo mientras la vida esté llena.
+    pub fn collect_near(&mut self, def: &LevelDef, pos: Vec2) -> Vec<PickupKind> {
+        let mut taken = Vec::new();
+        let mut i = 0;
+        while i < self.pickups.len() {
+            let p = &self.pickups[i];
+            let full = self.health >= MAX_HEALTH;
+            let wasted = matches!(p.kind, PickupKind::Item(id) if full && matches!(def.item(id).map(|d| &d.effect), Some(ItemEffect::Health(_))));
+            if p.pos.distance(pos) >= PICKUP_RADIUS || wasted {
+                i += 1;
+                continue;
+            }
+            let p = self.pickups.swap_remove(i);
+            match p.kind {
+                PickupKind::Friend(cell) => {
+                    if let Some(key) = def.collectible(cell).and_then(|c| c.key.as_ref()) {
+                        self.keys.insert(key.clone());
+                    }
+                    self.map.set(p.pos.x.floor() as i32, p.pos.y.floor() as i32, CELL_EMPTY);
+                    self.coins = (self.coins - 1).max(0);
+                    self.friends_found += 1;
+                }
+                PickupKind::Item(id) => {
+                    match def.item(id).map(|d| &d.effect) {
+                        Some(ItemEffect::Score(points)) => self.score += points,
+                        Some(ItemEffect::Key(key)) => {
+                            self.keys.insert(key.clone());
+                        }
+                        Some(ItemEffect::Health(amount)) => self.health = (self.health + amount).min(MAX_HEALTH),
+                        Some(&ItemEffect::Speed { factor, secs }) => self.speed_boost = Some(SpeedBoost { factor, remaining: secs }),
+                        None => {}
+                    }
+                    self.items_collected += 1;
+                }
+            }
+            taken.push(p.kind);
+        }
+        taken
+    }
+
+    /// Le quita `amount` de vida al jugador, salvo que siga invulnerable por un golpe reciente;
+    /// al llegar a 0 la partida está perdida. Devuelve si el golpe contó.
+    pub fn hurt(&mut self, amount: i32) -> bool {
+        if self.invulnerable > 0.0 || self.lost {
+            return false;
+        }
+        self.damage_taken += amount.min(self.health);
+        self.health = (self.health - amount).max(0);
+        self.invulnerable = INVULNERABLE_SECS;
+        if self.health == 0 {
+            self.lost = true;
+        }
+        true
+    }
+
+    /// Lastima al jugador si está parado sobre un peligro; devuelve el peligro si el golpe contó.
+    pub fn touch_hazard<'a>(&mut self, def: &'a LevelDef, pos: Vec2) -> Option<&'a HazardDef> {
+        let hazard = def.hazard_at(pos)?;
+        self.hurt(hazard.damage).then_some(hazard)
+    }
+
+    /// Avanza `dt` segundos el reloj de la partida y los efectos temporales (aumento de
+    /// velocidad, invulnerabilidad). Solo se llama mientras se juega, así las pausas no cuentan.
+    pub fn update_effects(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+        if let Some(boost) = &mut self.speed_boost {
+            boost.remaining -= dt;
+            if boost.remaining <= 0.0 {
+                self.speed_boost = None;
+            }
+        }
+    }
+
+    /// Multiplicador de la velocidad del jugador (1 sin aumento activo).
+    pub fn speed_factor(&self) -> f32 {
+        self.speed_boost.map_or(1.0, |b| b.factor)
+    }
+
+    /// Marca la victoria si `pos` está sobre la salida abierta. Devuelve `true` solo la primera vez.
+    pub fn reach_exit(&mut self, pos: Vec2) -> bool {
+        if self.won || self.coins > 0 {
+            return false;
+        }
+        if self.map.get(pos.x.floor() as i32, pos.y.floor() as i32) == Some(CELL_EXIT) {
+            self.won = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Sección de cuadrícula (`[mapa]`, `[suelo]`, `[techo]`) tal como aparece en el archivo.
+struct Section {
+    name: String,
+    header_line: usize,
+    rows: Vec<(usize, Vec<i32>)>,
+}
+
+impl Section {
+    /// Arma la cuadrícula comprobando que todas las filas tengan el mismo ancho
+    /// (y, si se indica, las dimensiones esperadas).
+    fn to_grid(&self, expected: Option<(usize, usize)>) -> Result<Grid, ParseError> {
+        if self.rows.is_empty() {
+            return Err(ParseError::new(self.header_line + 1, 1, format!("la sección `[{}]` está vacía", self.name)));
+        }
+        let width = expected.map(|(w, _)| w).unwrap_or(self.rows[0].1.len());
+        let mut cells = Vec::with_capacity(width * self.rows.len());
+        for (line_no, row) in &self.rows {
+            if row.len() != width {
+                let col = row.len().min(width) + 1;
+                return Err(ParseError::new(
+                    *line_no,
+                    col,
+                    format!("la fila tiene {} celdas pero se esperaban {}", row.len(), width),
+                ));
+            }
+            cells.extend_from_slice(row);
+        }
+        if let Some((_, h)) = expected
+            && self.rows.len() != h
+        {
+            let line = self.rows.last().map(|r| r.0).unwrap_or(self.header_line);
+            return Err(ParseError::new(
+                line,
+                1,
+                format!("`[{}]` tiene {} filas pero el mapa tiene {}", self.name, self.rows.len(), h),
+            ));
+        }
+        Ok(Grid::new(width, self.rows.len(), cells))
+    }
+}
+
+fn parse_row(line: &str, line_no: usize) -> Result<Vec<i32>, ParseError> {
+    line.chars()
+        .enumerate()
+        .map(|(col, ch)| {
+            ch.to_digit(10).map(|d| d as i32).ok_or_else(|| {
+                ParseError::new(line_no, col + 1, format!("celda inválida `{}` (se esperaba un dígito 0-9)", ch))
+            })
+        })
+        .collect()
+}
+
+fn parse_vec2(value: &str, line_no: usize, col: usize) -> Result<Vec2, ParseError> {
+    let nums: Vec<&str> = value.split_whitespace().collect();
+    if nums.len() != 2 {
+        return Err(ParseError::new(line_no, col, format!("se esperaban dos números, se encontró `{}`", value)));
+    }
+    let x = parse_f32(nums[0], line_no, col);
+    let y = parse_f32(nums[1], line_no, col);
+    match (x, y) {
+        (Ok(x), Ok(y)) => Ok(vec2(x, y)),
+        _ => Err(ParseError::new(line_no, col, format!("número inválido en `{}`", value))),
+    }
+}
+
+/// Campo `clave=valor` dentro del valor de una línea (p. ej. `sprite=burro escala=0.45`).
+struct Field<'a> {
+    key: &'a str,
+    value: &'a str,
+    column: usize,
+}
+
+fn parse_fields(value: &str, line_no: usize, col: usize) -> Result<Vec<Field<'_>>, ParseError> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for token in value.split_whitespace() {
+        let start = offset + value[offset..].find(token).unwrap_or(0);
+        offset = start + token.len();
+        let column = col + value[..start].chars().count();
+        let Some((k, v)) = token.split_once('=') else {
+            return Err(ParseError::new(line_no, column, format!("se esperaba `campo=valor`, se encontró `{}`", token)));
+        };
+        if v.is_empty() {
+            return Err(ParseError::new(line_no, column, format!("falta el valor del campo `{}`", k)));
+        }
+        fields.push(Field { key: k, value: v, column });
+    }
+    Ok(fields)
+}
+
+/// Campos de `amigo`, `salida` y `fantasma` que describen su sprite.
+const SPRITE_FIELDS: &[&str] = &["sprite", "escala", "bob", "angulos", "quieto", "camina", "fps", "mira", "opacidad"];
+
+fn parse_sprite(
+    fields: &[Field],
+    scale: f32,
+    bob_speed: f32,
+    bob_amount: f32,
+    line_no: usize,
+    col: usize,
+    texture_refs: &mut Vec<(String, usize, usize)>,
+) -> Result<SpriteDef, ParseError> {
+    let mut sprite = SpriteDef {
+        texture: String::new(),
+        scale,
+        bob_speed,
+        bob_amount,
+        angles: 1,
+        idle_frames: 1,
+        walk_frames: 0,
+        fps: 6.0,
+        facing: vec2(0.0, 1.0),
+        opacity: 1.0,
+    };
+    for f in fields {
+        let value_col = f.column + f.key.len() + 1;
+        match f.key {
+            "sprite" => {
+                texture_refs.push((f.value.to_string(), line_no, value_col));
+                sprite.texture = f.value.to_string();
+            }
+            "escala" => sprite.scale = parse_f32(f.value, line_no, value_col)?,
+            "bob" => {
+                let Some((speed, amount)) = f.value.split_once(',') else {
+                    return Err(ParseError::new(line_no, value_col, "se esperaba `bob=velocidad,amplitud`"));
+                };
+                sprite.bob_speed = parse_f32(speed, line_no, value_col)?;
+                sprite.bob_amount = parse_f32(amount, line_no, value_col + speed.len() + 1)?;
+            }
+            "angulos" | "quieto" | "camina" => {
+                let n = match f.value.parse::<usize>() {
+                    Ok(n) if n > 0 || f.key == "camina" => n,
+                    _ => {
+                        return Err(ParseError::new(line_no, value_col, format!("se esperaba un entero positivo, se encontró `{}`", f.value)));
+                    }
+                };
+                match f.key {
+                    "angulos" => sprite.angles = n,
+                    "quieto" => sprite.idle_frames = n,
+                    _ => sprite.walk_frames = n,
+                }
+            }
+            "fps" => sprite.fps = parse_f32(f.value, line_no, value_col)?,
+            "opacidad" => sprite.opacity = parse_opacity(f.value, line_no, value_col)?,
+            "mira" => {
+                let d = parse_vec2(&f.value.replace(',', " "), line_no, value_col)?;
+                if d.length_squared() == 0.0 {
+                    return Err(ParseError::new(line_no, value_col, "la dirección no puede ser (0, 0)"));
+                }
+                sprite.facing = d.normalize();
+            }
+            _ => {}
+        }
+    }
+    if sprite.texture.is_empty() {
+        return Err(ParseError::new(line_no, col, "falta el campo `sprite`"));
+    }
+    Ok(sprite)
+}
+
+/// Efecto y sonido de un `objeto`, según su `tipo=` y los campos que ese tipo admite.
+fn parse_item(
+    fields: &[Field],
+    line_no: usize,
+    col: usize,
+    sound_refs: &mut Vec<(String, usize, usize)>,
+) -> Result<(ItemEffect, Option<String>), ParseError> {
+    let Some(kind) = fields.iter().find(|f| f.key == "tipo") else {
+        return Err(ParseError::new(line_no, col, "falta el campo `tipo` (moneda, llave, cebolla o velocidad)"));
+    };
+    let mut effect = match kind.value {
+        "moneda" => ItemEffect::Score(10),
+        "llave" => ItemEffect::Key(String::new()),
+        "cebolla" => ItemEffect::Health(25),
+        "velocidad" => ItemEffect::Speed { factor: 1.6, secs: 5.0 },
+        other => {
+            return Err(ParseError::new(
+                line_no,
+                kind.column + kind.key.len() + 1,
+                format!("tipo de objeto desconocido `{}` (moneda, llave, cebolla o velocidad)", other),
+            ));
+        }
+    };
+    let mut sound = None;
+    for f in fields {
+        let value_col = f.column + f.key.len() + 1;
+        let positive = || match parse_f32(f.value, line_no, value_col) {
+            Ok(v) if v > 0.0 => Ok(v),
+            _ => Err(ParseError::new(line_no, value_col, format!("se esperaba un número positivo, se encontró `{}`", f.value))),
+        };
+        match (f.key, &mut effect) {
+            ("tipo", _) => {}
+            ("sonido", _) => {
+                sound_refs.push((f.value.to_string(), line_no, f.column));
+                sound = Some(f.value.to_string());
+            }
+            ("valor", ItemEffect::Score(points)) => match f.value.parse::<u32>() {
+                Ok(n) if n > 0 => *points = n,
+                _ => return Err(ParseError::new(line_no, value_col, format!("valor inválido `{}`", f.value))),
+            },
+            ("llave", ItemEffect::Key(key)) => *key = f.value.to_string(),
+            ("vida", ItemEffect::Health(amount)) => match f.value.parse::<i32>() {
+                Ok(n) if n > 0 => *amount = n,
+                _ => return Err(ParseError::new(line_no, value_col, format!("vida inválida `{}`", f.value))),
+            },
+            ("factor", ItemEffect::Speed { factor, .. }) => *factor = positive()?,
+            ("duracion", ItemEffect::Speed { secs, .. }) => *secs = positive()?,
+            (k, _) if SPRITE_FIELDS.contains(&k) => {}
+            (other, _) => {
+                return Err(ParseError::new(
+                    line_no,
+                    f.column,
+                    format!("campo desconocido `{}` para un objeto de tipo {}", other, kind.value),
+                ));
+            }
+        }
+    }
+    if effect == ItemEffect::Key(String::new()) {
+        return Err(ParseError::new(line_no, col, "un objeto de tipo llave necesita `llave=<nombre>`"));
+    }
+    Ok((effect, sound))
+}
+
+/// Ronda de un enemigo: celdas `x,y` separadas por `;`.
+fn parse_route(value: &str, line_no: usize, col: usize) -> Result<Vec<(i32, i32)>, ParseError> {
+    value
+        .split(';')
+        .map(|point| {
+            let cell = point.split_once(',').and_then(|(x, y)| Some((x.parse::<i32>().ok()?, y.parse::<i32>().ok()?)));
+            cell.ok_or_else(|| ParseError::new(line_no, col, format!("celda inválida `{}` en la ruta (se esperaba `x,y`)", point)))
+        })
+        .collect()
+}
+
+fn parse_wall_faces(
+    value: &str,
+    line_no: usize,
+    col: usize,
+    texture_refs: &mut Vec<(String, usize, usize)>,
+) -> Result<WallFaces, ParseError> {
+    // forma corta: `pared 2 = bosque`
+    if !value.contains('=') {
+        texture_refs.push((value.to_string(), line_no, col));
+        let name = value.to_string();
+        return Ok(WallFaces { north: name.clone(), south: name.clone(), east: name.clone(), west: name, opacity: 1.0 });
+    }
+    let mut all = None;
+    let mut faces: [Option<String>; 4] = Default::default();
+    let mut opacity = 1.0;
+    for f in parse_fields(value, line_no, col)? {
+        let value_col = f.column + f.key.len() + 1;
+        if f.key == "opacidad" {
+            opacity = parse_opacity(f.value, line_no, value_col)?;
+            continue;
+        }
+        let slot = match f.key {
+            "textura" => &mut all,
+            "norte" => &mut faces[0],
+            "sur" => &mut faces[1],
+            "este" => &mut faces[2],
+            "oeste" => &mut faces[3],
+            other => {
+                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+            }
+        };
+        texture_refs.push((f.value.to_string(), line_no, value_col));
+        *slot = Some(f.value.to_string());
+    }
+    let [north, south, east, west] = faces.map(|face| face.or_else(|| all.clone()));
+    match (north, south, east, west) {
+        (Some(north), Some(south), Some(east), Some(west)) => Ok(WallFaces { north, south, east, west, opacity }),
+        _ => Err(ParseError::new(line_no, col, "falta `textura=` o la textura de alguna cara")),
+    }
+}
+
+fn parse_animation(value: &str, line_no: usize, col: usize) -> Result<AnimationDef, ParseError> {
+    let mut anim = AnimationDef { frames: 0, columns: 0, fps: 0.0 };
+    for f in parse_fields(value, line_no, col)? {
+        let value_col = f.column + f.key.len() + 1;
+        let count = || match f.value.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(ParseError::new(line_no, value_col, format!("se esperaba un entero positivo, se encontró `{}`", f.value))),
+        };
+        match f.key {
+            "cuadros" => anim.frames = count()?,
+            "columnas" => anim.columns = count()?,
+            "fps" => anim.fps = parse_f32(f.value, line_no, value_col)?,
+            other => {
+                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+            }
+        }
+    }
+    if anim.frames == 0 || anim.fps <= 0.0 {
+        return Err(ParseError::new(line_no, col, "faltan `cuadros=` o `fps=` (mayor que 0)"));
+    }
+    // por defecto, todos los cuadros en una sola fila
+    if anim.columns == 0 {
+        anim.columns = anim.frames;
+    }
+    Ok(anim)
+}
+
+fn parse_fog(value: &str, line_no: usize, col: usize) -> Result<FogDef, ParseError> {
+    let mut fog = FogDef { color: (0, 0, 0), start: 0.0, end: 0.0 };
+    let mut end = None;
+    for f in parse_fields(value, line_no, col)? {
+        let value_col = f.column + f.key.len() + 1;
+        match f.key {
+            "color" => fog.color = parse_color(f.value, line_no, value_col)?,
+            "inicio" => fog.start = parse_f32(f.value, line_no, value_col)?,
+            "fin" => end = Some((parse_f32(f.value, line_no, value_col)?, value_col)),
+            other => {
+                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+            }
+        }
+    }
+    let Some((end, end_col)) = end else {
+        return Err(ParseError::new(line_no, col, "falta el campo `fin`"));
+    };
+    if end <= fog.start {
+        return Err(ParseError::new(line_no, end_col, "`fin` tiene que ser mayor que `inicio`"));
+    }
+    fog.end = end;
+    Ok(fog)
+}
+
+fn parse_light(value: &str, line_no: usize, col: usize) -> Result<LightDef, ParseError> {
+    let mut source = None;
+    let mut light = LightDef {
+        source: LightSource::Cell(CELL_EMPTY),
+        color: (255, 255, 255),
+        radius: 0.0,
+        intensity: 1.0,
+        flicker: 0.0,
+    };
+    for f in parse_fields(value, line_no, col)? {
+        let value_col = f.column + f.key.len() + 1;
+        match f.key {
+            "pos" => {
+                let Some((x, y)) = f.value.split_once(',') else {
+                    return Err(ParseError::new(line_no, value_col, "se esperaba `pos=x,y`"));
+                };
+                let pos = vec2(parse_f32(x, line_no, value_col)?, parse_f32(y, line_no, value_col + x.len() + 1)?);
+                source = Some(LightSource::Pos(pos));
+            }
+            "celda" => {
+                let cell = match f.value.parse::<i32>() {
+                    Ok(c) if (0..=9).contains(&c) && c != CELL_EMPTY && c != CELL_WALL => c,
+                    _ => {
+                        return Err(ParseError::new(line_no, value_col, format!("código de celda inválido `{}`", f.value)));
+                    }
+                };
+                source = Some(LightSource::Cell(cell));
+            }
+            "color" => light.color = parse_color(f.value, line_no, value_col)?,
+            "radio" => light.radius = parse_f32(f.value, line_no, value_col)?,
+            "intensidad" => light.intensity = parse_f32(f.value, line_no, value_col)?,
+            "parpadeo" => {
+                light.flicker = parse_f32(f.value, line_no, value_col)?;
+                if !(0.0..=1.0).contains(&light.flicker) {
+                    return Err(ParseError::new(line_no, value_col, "`parpadeo` va de 0 a 1"));
+                }
+            }
+            other => {
+                return Err(ParseError::new(line_no, f.column, format!("campo desconocido `{}`", other)));
+            }
+        }
+    }
+    let Some(source) = source else {
+        return Err(ParseError::new(line_no, col, "falta `pos=x,y` o `celda=<código>`"));
+    };
+    if light.radius <= 0.0 {
+        return Err(ParseError::new(line_no, col, "falta `radio` (mayor que 0)"));
+    }
+    light.source = source;
+    Ok(light)
+}
+
+fn parse_color(value: &str, line_no: usize, col: usize) -> Result<(u8, u8, u8), ParseError> {
+    let channels: Option<Vec<u8>> = value.split(',').map(|c| c.parse::<u8>().ok()).collect();
+    match channels.as_deref() {
+        Some(&[r, g, b]) => Ok((r, g, b)),
+        _ => Err(ParseError::new(line_no, col, "se esperaba `color=r,g,b` (0-255)")),
+    }
+}
+
+/// Número con decimales; `NaN` e infinitos no valen (pasarían de largo las comparaciones de rango).
+fn parse_f32(value: &str, line_no: usize, col: usize) -> Result<f32, ParseError> {
+    match value.parse::<f32>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(ParseError::new(line_no, col, format!("número inválido `{}`", value))),
+    }
+}
+
+fn parse_opacity(value: &str, line_no: usize, col: usize) -> Result<f32, ParseError> {
+    match value.parse::<f32>() {
+        Ok(v) if v > 0.0 && v <= 1.0 => Ok(v),
+        _ => Err(ParseError::new(line_no, col, format!("la opacidad va de 0 (sin incluir) a 1, se encontró `{}`", value))),
+    }
+}
+
+fn parse_bool(value: &str, line_no: usize, col: usize) -> Result<bool, ParseError> {
+    match value {
+        "si" | "sí" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(ParseError::new(line_no, col, format!("se esperaba `si` o `no`, se encontró `{}`", value))),
+    }
+}
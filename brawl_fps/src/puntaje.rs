@@ -0,0 +1,72 @@
+use crate::nivel::{LevelDef, LevelState};
+
+// Puntaje de una partida: se arma al terminar a partir de las estadísticas que junta
+// `LevelState` (tiempo sin pausas, amigos, objetos, daño). Cada fuente de puntos es una línea
+// del desglose que se muestra al ganar; el total es su suma, sin bajar de 0.
+
+/// Puntos por cada amigo encontrado.
+pub const FRIEND_POINTS: i32 = 100;
+/// Puntos por cada segundo por debajo del tiempo par del nivel.
+pub const PAR_POINTS_PER_SEC: f32 = 10.0;
+/// Puntos que se restan por cada punto de vida perdido.
+pub const DAMAGE_PENALTY: i32 = 2;
+
+/// Una línea del desglose: qué se cuenta y cuántos puntos vale (negativos si resta).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreLine {
+    pub label: String,
+    pub points: i32,
+}
+
+/// Resultado de una partida terminada.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResults {
+    /// Segundos jugados, sin contar las pausas.
+    pub time: f32,
+    pub par_time: Option<f32>,
+    pub friends: u32,
+    pub items: u32,
+    pub damage: i32,
+    pub lines: Vec<ScoreLine>,
+    pub total: u32,
+}
+
+impl RunResults {
+    /// Calcula el puntaje de la partida `state` del nivel `def`.
+    pub fn new(def: &LevelDef, state: &LevelState) -> Self {
+        let mut lines = vec![ScoreLine {
+            label: format!("Amigos encontrados: {}", state.friends_found),
+            points: state.friends_found as i32 * FRIEND_POINTS,
+        }];
+        if !def.items.is_empty() {
+            lines.push(ScoreLine { label: format!("Objetos recogidos: {}", state.items_collected), points: state.score.min(i32::MAX as u32) as i32 });
+        }
+        if let Some(par) = def.par_time {
+            let under = (par - state.elapsed).max(0.0);
+            lines.push(ScoreLine {
+                label: format!("Tiempo: {} (par {})", format_time(state.elapsed), format_time(par)),
+                points: (under * PAR_POINTS_PER_SEC).round() as i32,
+            });
+        }
+        if def.has_damage() {
+            lines.push(ScoreLine { label: format!("Daño recibido: {}", state.damage_taken), points: -state.damage_taken * DAMAGE_PENALTY });
+        }
+        // en i64 para que un nivel con muchos puntos no desborde la suma
+        let total = lines.iter().map(|l| l.points as i64).sum::<i64>().clamp(0, u32::MAX as i64) as u32;
+        Self {
+            time: state.elapsed,
+            par_time: def.par_time,
+            friends: state.friends_found,
+            items: state.items_collected,
+            damage: state.damage_taken,
+            lines,
+            total,
+        }
+    }
+}
+
+/// Formatea segundos como `m:ss`.
+pub fn format_time(secs: f32) -> String {
+    let secs = secs.max(0.0) as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,1056 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+use crate::luces::{LightGrid, LightMap};
+use crate::nivel::{AnimationDef, Door, DoorAxis, FogDef, Grid, LevelDef, LevelState, PickupKind, SpriteDef, CELL_EXIT};
+
+// Renderer por software: hace el raycasting sobre un framebuffer RGBA en memoria (`Vec<u32>`),
+// así se puede usar tanto para la ventana del juego (se sube como textura) como fuera de
+// pantalla (tests, capturas) sin necesitar GPU.
+
+pub const FOV: f32 = 0.66; // ~66°
+/// Factor de brillo de las caras de pared que el rayo cruza en y, para que las esquinas se lean.
+pub const SIDE_SHADE: f32 = 0.7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub pos: Vec2,
+    pub dir: Vec2,
+    pub plane: Vec2,
+}
+
+impl Camera {
+    pub fn new(pos: Vec2, dir: Vec2) -> Self {
+        // el plano de cámara es perpendicular a `dir`
+        let plane = vec2(-dir.y, dir.x) * FOV;
+        Self { pos, dir, plane }
+    }
+
+    pub fn rotate(&mut self, angle: f32) {
+        let (sin_a, cos_a) = angle.sin_cos();
+        let old_dir_x = self.dir.x;
+        self.dir.x = self.dir.x * cos_a - self.dir.y * sin_a;
+        self.dir.y = old_dir_x * sin_a + self.dir.y * cos_a;
+        let old_plane_x = self.plane.x;
+        self.plane.x = self.plane.x * cos_a - self.plane.y * sin_a;
+        self.plane.y = old_plane_x * sin_a + self.plane.y * cos_a;
+    }
+
+    /// Cámara a una fracción `alpha` (0..1) del camino entre `self` y `to`. El plano conserva el
+    /// campo de visión de `to`.
+    pub fn lerp(&self, to: &Camera, alpha: f32) -> Camera {
+        let dir = self.dir.lerp(to.dir, alpha).try_normalize().unwrap_or(to.dir);
+        let mut cam = Camera::new(self.pos.lerp(to.pos, alpha), dir);
+        cam.plane = vec2(-dir.y, dir.x) * to.plane.length();
+        cam
+    }
+}
+
+/// Empaqueta un color RGBA (0..255) como `0xAARRGGBB`.
+pub const fn pack_rgba(r: u8, g: u8, b: u8, a: u8) -> u32 {
+    ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+/// Convierte un `Color` de macroquad al formato del framebuffer.
+pub fn pack_color(c: Color) -> u32 {
+    let [r, g, b, a]: [u8; 4] = c.into();
+    pack_rgba(r, g, b, a)
+}
+
+/// Mezcla `src` sobre `dst` según el alfa de `src` (el resultado es opaco).
+fn blend(dst: u32, src: u32) -> u32 {
+    let a = src >> 24;
+    if a == 255 {
+        return src;
+    }
+    if a == 0 {
+        return dst;
+    }
+    let inv = 255 - a;
+    let ch = |shift: u32| {
+        let s = (src >> shift) & 0xff;
+        let d = (dst >> shift) & 0xff;
+        ((s * a + d * inv) / 255) << shift
+    };
+    0xff00_0000 | ch(16) | ch(8) | ch(0)
+}
+
+/// Multiplica cada canal de `p` por la luz (RGB, puede pasar de 1), conservando el alfa.
+fn lit(p: u32, light: Vec3) -> u32 {
+    let ch = |shift: u32, k: f32| ((((p >> shift) & 0xff) as f32 * k) as u32).min(255) << shift;
+    (p & 0xff00_0000) | ch(16, light.x) | ch(8, light.y) | ch(0, light.z)
+}
+
+/// Multiplica el alfa de `p` por `opacity` (0..1).
+fn fade(p: u32, opacity: f32) -> u32 {
+    if opacity >= 1.0 {
+        return p;
+    }
+    let a = ((p >> 24) as f32 * opacity) as u32;
+    (a << 24) | (p & 0x00ff_ffff)
+}
+
+/// Interpola el RGB de `p` hacia `target` (`f` = 0 deja `p`, 1 da `target`), conservando el alfa de `p`.
+fn lerp_rgb(p: u32, target: u32, f: f32) -> u32 {
+    let ch = |shift: u32| {
+        let a = ((p >> shift) & 0xff) as f32;
+        let b = ((target >> shift) & 0xff) as f32;
+        ((a + (b - a) * f) as u32).min(255) << shift
+    };
+    (p & 0xff00_0000) | ch(16) | ch(8) | ch(0)
+}
+
+/// Niebla por distancia ya lista para aplicar a píxeles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub color: u32,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Fog {
+    pub fn from_def(def: &FogDef) -> Self {
+        let (r, g, b) = def.color;
+        Self { color: pack_rgba(r, g, b, 255), start: def.start, end: def.end }
+    }
+
+    /// Cuánto cubre la niebla a distancia `dist` (0 = nada, 1 = solo niebla).
+    fn amount(&self, dist: f32) -> f32 {
+        ((dist - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+    }
+
+    fn apply(&self, p: u32, dist: f32) -> u32 {
+        let f = self.amount(dist);
+        if f <= 0.0 { p } else { lerp_rgb(p, self.color, f) }
+    }
+}
+
+/// Aplica la niebla opcional de la escena.
+fn fogged(fog: Option<&Fog>, p: u32, dist: f32) -> u32 {
+    match fog {
+        Some(f) => f.apply(p, dist),
+        None => p,
+    }
+}
+
+/// Imagen en memoria (píxeles `0xAARRGGBB`, fila a fila) usada como textura por el renderer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureData {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+}
+
+impl TextureData {
+    pub fn from_rgba8(width: usize, height: usize, bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), width * height * 4, "tamaño de imagen inconsistente");
+        let pixels = bytes.chunks_exact(4).map(|p| pack_rgba(p[0], p[1], p[2], p[3])).collect();
+        Self { width, height, pixels }
+    }
+
+    pub fn from_image(img: &Image) -> Self {
+        Self::from_rgba8(img.width as usize, img.height as usize, &img.bytes)
+    }
+
+    /// Decodifica un PNG (u otro formato soportado) leído desde disco, sin pasar por la GPU.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let img = Image::from_file_with_format(&bytes, None).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self::from_image(&img))
+    }
+
+    /// Textura 2x2 de un solo color (placeholder cuando falta una imagen).
+    pub fn solid(r: u8, g: u8, b: u8) -> Self {
+        Self { width: 2, height: 2, pixels: vec![pack_rgba(r, g, b, 255); 4] }
+    }
+
+    /// Recorta el rectángulo `(x, y, w, h)` (en píxeles) como textura aparte.
+    pub fn sub_image(&self, x: usize, y: usize, w: usize, h: usize) -> Self {
+        let mut pixels = Vec::with_capacity(w * h);
+        for row in y..y + h {
+            pixels.extend_from_slice(&self.pixels[row * self.width + x..row * self.width + x + w]);
+        }
+        Self { width: w, height: h, pixels }
+    }
+
+    /// Parte un atlas en `count` cuadros iguales, en filas de `columns`, de izquierda a derecha.
+    pub fn split_frames(&self, count: usize, columns: usize) -> Vec<Self> {
+        let rows = count.div_ceil(columns);
+        let (fw, fh) = (self.width / columns, self.height / rows);
+        if fw == 0 || fh == 0 {
+            return vec![self.clone()];
+        }
+        (0..count).map(|i| self.sub_image((i % columns) * fw, (i / columns) * fh, fw, fh)).collect()
+    }
+
+    /// Si todos sus píxeles son opacos.
+    pub fn is_opaque(&self) -> bool {
+        self.pixels.iter().all(|p| p >> 24 == 255)
+    }
+
+    /// Muestreo "nearest" con coordenadas normalizadas (0..1), clampeadas al borde.
+    pub fn sample(&self, u: f32, v: f32) -> u32 {
+        let tx = ((u * self.width as f32) as isize).clamp(0, self.width as isize - 1) as usize;
+        let ty = ((v * self.height as f32) as isize).clamp(0, self.height as isize - 1) as usize;
+        self.pixels[ty * self.width + tx]
+    }
+}
+
+/// Framebuffer RGBA en memoria.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![pack_rgba(0, 0, 0, 255); width * height] }
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: u32) {
+        for row in y.min(self.height)..(y + h).min(self.height) {
+            let start = row * self.width;
+            self.pixels[start + x.min(self.width)..start + (x + w).min(self.width)].fill(color);
+        }
+    }
+
+    /// Copia de los píxeles como bytes RGBA (para subirlos a una textura o guardarlos en PNG).
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for &p in &self.pixels {
+            bytes.extend_from_slice(&[(p >> 16) as u8, (p >> 8) as u8, p as u8, (p >> 24) as u8]);
+        }
+        bytes
+    }
+
+    pub fn to_image(&self) -> Image {
+        Image { bytes: self.to_rgba8(), width: self.width as u16, height: self.height as u16 }
+    }
+
+    /// Guarda el framebuffer como PNG (capturas de pantalla, imágenes de referencia).
+    pub fn save_png(&self, path: &str) {
+        // `Image::export_png` invierte las filas (pensado para render targets de GPU),
+        // así que las invertimos antes para que el archivo quede al derecho.
+        let mut img = self.to_image();
+        let row = self.width * 4;
+        img.bytes = img.bytes.chunks_exact(row).rev().flatten().copied().collect();
+        img.export_png(path);
+    }
+}
+
+/// Secuencia de cuadros de una textura (uno solo si es estática).
+struct AnimatedTexture {
+    frames: Vec<TextureData>,
+    fps: f32,
+    /// Si todos los cuadros son opacos (si no, la pared deja ver lo de atrás).
+    opaque: bool,
+}
+
+impl AnimatedTexture {
+    fn new(tex: &TextureData, anim: Option<&AnimationDef>) -> Self {
+        let (frames, fps) = match anim {
+            Some(a) => (tex.split_frames(a.frames, a.columns), a.fps),
+            None => (vec![tex.clone()], 0.0),
+        };
+        let opaque = frames.iter().all(TextureData::is_opaque);
+        Self { frames, fps, opaque }
+    }
+
+    /// Cuadro que corresponde al instante `t` (segundos).
+    fn frame(&self, t: f32) -> &TextureData {
+        let i = (t.max(0.0) * self.fps) as usize % self.frames.len();
+        &self.frames[i]
+    }
+}
+
+/// Todas las texturas del nivel en un solo lugar, referenciadas por índice. Las declaradas con
+/// `animacion` se parten en cuadros y se muestrean según el tiempo.
+/// El índice 0 es el placeholder magenta que se usa para lo que falte.
+pub struct TextureRegistry {
+    textures: Vec<AnimatedTexture>,
+    by_name: HashMap<String, usize>,
+}
+
+impl TextureRegistry {
+    pub fn new(textures: &HashMap<String, TextureData>, animations: &HashMap<String, AnimationDef>) -> Self {
+        let mut names: Vec<&String> = textures.keys().collect();
+        names.sort();
+        let placeholder = AnimatedTexture::new(&TextureData::solid(255, 0, 255), None);
+        let mut registry = Self { textures: vec![placeholder], by_name: HashMap::new() };
+        for name in names {
+            registry.by_name.insert(name.clone(), registry.textures.len());
+            registry.textures.push(AnimatedTexture::new(&textures[name], animations.get(name)));
+        }
+        registry
+    }
+
+    /// Índice de la textura `name` (0 = placeholder si no está).
+    pub fn index(&self, name: &str) -> usize {
+        self.by_name.get(name).copied().unwrap_or(0)
+    }
+
+    /// Cuadro de la textura `index` en el instante `t`.
+    pub fn get(&self, index: usize, t: f32) -> &TextureData {
+        self.textures[index].frame(t)
+    }
+
+    /// Si la textura `index` es opaca en todos sus cuadros.
+    pub fn is_opaque(&self, index: usize) -> bool {
+        self.textures[index].opaque
+    }
+}
+
+/// Cara de una pared, según desde dónde llega el rayo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Face {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Face {
+    /// Cara que toca un rayo que avanzó por última vez en x (`side` 0) o en y (`side` 1).
+    fn hit(side: i32, step_x: i32, step_y: i32) -> Self {
+        match (side, step_x, step_y) {
+            (0, 1, _) => Face::West,
+            (0, _, _) => Face::East,
+            (_, _, 1) => Face::North,
+            _ => Face::South,
+        }
+    }
+}
+
+/// Texturas de las caras de cada celda del mapa (norte, sur, este, oeste), como índices del registro,
+/// y su opacidad.
+pub struct WallTextures {
+    registry: TextureRegistry,
+    width: usize,
+    faces: Vec<[usize; 4]>,
+    opacity: Vec<f32>,
+}
+
+impl WallTextures {
+    pub fn from_level(def: &LevelDef, registry: TextureRegistry) -> Self {
+        let (width, height) = (def.grid.width, def.grid.height);
+        let walls: Vec<_> = (0..width * height).map(|i| def.wall_faces((i % width) as i32, (i / width) as i32)).collect();
+        let faces = walls
+            .iter()
+            .map(|w| match w {
+                Some(f) => [&f.north, &f.south, &f.east, &f.west].map(|name| registry.index(name)),
+                None => [0; 4],
+            })
+            .collect();
+        let opacity = walls.iter().map(|w| w.map_or(1.0, |f| f.opacity)).collect();
+        Self { registry, width, faces, opacity }
+    }
+
+    fn cell(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width {
+            return None;
+        }
+        let i = y as usize * self.width + x as usize;
+        (i < self.faces.len()).then_some(i)
+    }
+
+    /// Opacidad de la pared de la celda, o `None` si es opaca del todo (ni `opacidad=` ni
+    /// texturas con huecos): el rayo se detiene en ella.
+    pub fn see_through(&self, x: i32, y: i32) -> Option<f32> {
+        let i = self.cell(x, y)?;
+        let opaque = self.opacity[i] >= 1.0 && self.faces[i].iter().all(|&tex| self.registry.is_opaque(tex));
+        (!opaque).then_some(self.opacity[i])
+    }
+
+    pub fn registry(&self) -> &TextureRegistry {
+        &self.registry
+    }
+
+    pub fn for_face(&self, x: i32, y: i32, face: Face, t: f32) -> &TextureData {
+        let index = self.cell(x, y).map(|i| self.faces[i][face as usize]);
+        self.registry.get(index.unwrap_or(0), t)
+    }
+}
+
+/// Sprite listo para dibujar: cuadros por vista y animación + escala respecto a una pared y bob vertical.
+pub struct SpriteRender {
+    /// Cuadros en orden de atlas: `frames[fila * angles + vista]`.
+    pub frames: Vec<TextureData>,
+    pub angles: usize,
+    pub idle_frames: usize,
+    pub walk_frames: usize,
+    pub fps: f32,
+    /// Hacia dónde mira si la entidad no dice otra cosa (amigos quietos, salida).
+    pub facing: Vec2,
+    pub scale: f32,
+    pub bob_speed: f32,
+    pub bob_amount: f32,
+    /// 1 = opaco; con menos se mezcla con lo que hay detrás.
+    pub opacity: f32,
+    /// Apoyado en el suelo en vez de centrado a la altura de los ojos (objetos).
+    pub grounded: bool,
+}
+
+impl SpriteRender {
+    /// Sprite de un solo cuadro, igual desde todos los ángulos.
+    pub fn still(tex: TextureData, scale: f32, bob_speed: f32, bob_amount: f32) -> Self {
+        Self {
+            frames: vec![tex],
+            angles: 1,
+            idle_frames: 1,
+            walk_frames: 0,
+            fps: 0.0,
+            facing: vec2(0.0, 1.0),
+            scale,
+            bob_speed,
+            bob_amount,
+            opacity: 1.0,
+            grounded: false,
+        }
+    }
+
+    /// Cuadro a mostrar para una entidad que mira hacia `facing`, vista desde `to_camera`
+    /// (vector de la entidad a la cámara), caminando o no, en el instante `t` (segundos).
+    pub fn frame(&self, to_camera: Vec2, facing: Vec2, walking: bool, t: f32) -> &TextureData {
+        let view = if self.angles > 1 {
+            // 0 = de frente; crece girando hacia la derecha del personaje
+            let rel = (to_camera.y.atan2(to_camera.x) - facing.y.atan2(facing.x)).rem_euclid(std::f32::consts::TAU);
+            (rel / std::f32::consts::TAU * self.angles as f32).round() as usize % self.angles
+        } else {
+            0
+        };
+        let tick = (t * self.fps).max(0.0) as usize;
+        let row = if walking && self.walk_frames > 0 {
+            self.idle_frames + tick % self.walk_frames
+        } else {
+            tick % self.idle_frames.max(1)
+        };
+        let i = row * self.angles + view;
+        self.frames.get(i).unwrap_or(&self.frames[0])
+    }
+}
+
+/// Texturas de suelo y techo por celda, más el cielo panorámico opcional.
+/// Sin capas ni cielo se pintan los colores planos de siempre (techo azul, suelo gris).
+#[derive(Default)]
+pub struct Surfaces {
+    pub floor: Option<Grid>,
+    pub ceiling: Option<Grid>,
+    pub floor_textures: HashMap<i32, TextureData>,
+    pub ceiling_textures: HashMap<i32, TextureData>,
+    pub sky: Option<TextureData>,
+}
+
+impl Surfaces {
+    fn is_flat(&self) -> bool {
+        self.floor.is_none() && self.ceiling.is_none() && self.sky.is_none()
+    }
+
+    fn texture_at<'a>(ids: &Option<Grid>, textures: &'a HashMap<i32, TextureData>, x: i32, y: i32) -> Option<&'a TextureData> {
+        let id = ids.as_ref()?.get(x, y)?;
+        textures.get(&id)
+    }
+}
+
+/// Altura de pared de cada celda (1 = normal) y la más alta del nivel.
+pub struct WallHeights {
+    width: usize,
+    values: Vec<f32>,
+    pub max: f32,
+}
+
+impl WallHeights {
+    pub fn from_level(def: &LevelDef) -> Self {
+        let (width, height) = (def.grid.width, def.grid.height);
+        let values: Vec<f32> = (0..height * width).map(|i| def.wall_height((i % width) as i32, (i / width) as i32)).collect();
+        let max = values.iter().copied().fold(1.0, f32::max);
+        Self { width, values, max }
+    }
+
+    pub fn at(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 || x as usize >= self.width {
+            return 1.0;
+        }
+        self.values.get(y as usize * self.width + x as usize).copied().unwrap_or(1.0)
+    }
+}
+
+/// Todo lo que el raycaster necesita dibujar de un nivel.
+pub struct SceneAssets {
+    pub walls: WallTextures,
+    pub surfaces: Surfaces,
+    pub heights: WallHeights,
+    /// Sprites por código de celda (coleccionables y salida).
+    pub sprites: HashMap<i32, SpriteRender>,
+    /// Sprites por id de objeto.
+    pub item_sprites: HashMap<i32, SpriteRender>,
+    /// Sprite de cada enemigo, en el orden de `LevelDef::enemies`.
+    pub enemy_sprites: Vec<SpriteRender>,
+    /// Sprite del fantasma de la mejor partida, si el nivel lo declara.
+    pub ghost: Option<SpriteRender>,
+    /// Textura de la hoja por código de puerta.
+    pub doors: HashMap<i32, TextureData>,
+    pub fog: Option<Fog>,
+    pub lights: LightMap,
+}
+
+impl SceneAssets {
+    /// Arma los recursos de render de un nivel a partir de sus texturas ya cargadas por nombre.
+    /// Las texturas que falten se sustituyen por un placeholder magenta.
+    pub fn from_level(def: &LevelDef, textures: &HashMap<String, TextureData>) -> Self {
+        // fuera de las paredes, las texturas animadas se ven en su primer cuadro
+        let lookup = |name: &str| match textures.get(name) {
+            Some(t) => match def.animations.get(name) {
+                Some(a) => t.split_frames(a.frames, a.columns).swap_remove(0),
+                None => t.clone(),
+            },
+            None => {
+                eprintln!("Warning: el nivel no declara la textura `{}`. Usando placeholder.", name);
+                TextureData::solid(255, 0, 255)
+            }
+        };
+
+        // paredes: textura por celda y cara, declarada en el nivel
+        let walls = WallTextures::from_level(def, TextureRegistry::new(textures, &def.animations));
+
+        let surfaces = Surfaces {
+            floor: def.floor.clone(),
+            ceiling: def.ceiling.clone(),
+            floor_textures: def.floor_textures.iter().map(|(&id, name)| (id, lookup(name))).collect(),
+            ceiling_textures: def.ceiling_textures.iter().map(|(&id, name)| (id, lookup(name))).collect(),
+            sky: def.sky.as_deref().map(lookup),
+        };
+
+        // sprites de una sola imagen o atlas de vistas × cuadros
+        let sprite_render = |s: &SpriteDef| {
+            let mut sprite = if s.angles == 1 && s.idle_frames == 1 && s.walk_frames == 0 {
+                SpriteRender::still(lookup(&s.texture), s.scale, s.bob_speed, s.bob_amount)
+            } else {
+                let count = s.angles * (s.idle_frames + s.walk_frames);
+                let frames = match textures.get(&s.texture) {
+                    Some(t) => t.split_frames(count, s.angles),
+                    None => vec![lookup(&s.texture)],
+                };
+                SpriteRender {
+                    frames,
+                    angles: s.angles,
+                    idle_frames: s.idle_frames,
+                    walk_frames: s.walk_frames,
+                    fps: s.fps,
+                    facing: s.facing,
+                    scale: s.scale,
+                    bob_speed: s.bob_speed,
+                    bob_amount: s.bob_amount,
+                    opacity: 1.0,
+                    grounded: false,
+                }
+            };
+            sprite.opacity = s.opacity;
+            sprite
+        };
+
+        // sprites por código de celda (coleccionables + salida) y por id de objeto
+        let sprites = def
+            .collectibles
+            .iter()
+            .map(|c| (c.cell, &c.sprite))
+            .chain(def.exit_sprite.iter().map(|s| (CELL_EXIT, s)))
+            .map(|(cell, s)| (cell, sprite_render(s)))
+            .collect();
+        let item_sprites = def.items.iter().map(|i| (i.id, SpriteRender { grounded: true, ..sprite_render(&i.sprite) })).collect();
+        let enemy_sprites = def.enemies.iter().map(|e| SpriteRender { grounded: true, ..sprite_render(&e.sprite) }).collect();
+        let ghost = def.ghost_sprite.as_ref().map(|s| SpriteRender { grounded: true, ..sprite_render(s) });
+
+        let doors = def.doors.iter().map(|d| (d.cell, lookup(&d.texture))).collect();
+
+        Self {
+            walls,
+            surfaces,
+            heights: WallHeights::from_level(def),
+            sprites,
+            item_sprites,
+            enemy_sprites,
+            ghost,
+            doors,
+            fog: def.fog.as_ref().map(Fog::from_def),
+            lights: LightMap::bake(def),
+        }
+    }
+}
+
+/// Pinta suelo y techo. Con capas de texturas hace "floor casting" por fila: cada fila de
+/// pantalla bajo el horizonte corresponde a una distancia fija, y recorre el suelo entre los
+/// rayos de los bordes izquierdo y derecho (el techo es la fila espejada). Donde no hay techo
+/// y el nivel tiene `cielo`, se muestra la panorámica según el ángulo de cada columna.
+/// La luz se muestrea en cada punto del suelo y la niebla según la distancia de cada fila
+/// (el cielo queda sin luz ni niebla).
+fn render_floor_ceiling(fb: &mut Framebuffer, cam: &Camera, surfaces: &Surfaces, light: &LightGrid, fog: Option<&Fog>) {
+    let w = fb.width;
+    let h = fb.height;
+    let floor_color = pack_color(DARKGRAY);
+    let ceiling_color = pack_color(DARKBLUE);
+    if surfaces.is_flat() && fog.is_none() && light.is_uniform() {
+        fb.fill_rect(0, 0, w, h / 2, ceiling_color);
+        fb.fill_rect(0, h / 2, w, h - h / 2, floor_color);
+        return;
+    }
+
+    let sh = h as f32;
+    let ray_left = cam.dir - cam.plane;
+    let ray_right = cam.dir + cam.plane;
+
+    // coordenada horizontal de la panorámica por columna (una vuelta completa = toda la imagen)
+    let sky_u: Vec<f32> = (0..w)
+        .map(|x| {
+            let camera_x = 2.0 * x as f32 / w as f32 - 1.0;
+            let ray = cam.dir + cam.plane * camera_x;
+            (ray.y.atan2(ray.x) / std::f32::consts::TAU).rem_euclid(1.0)
+        })
+        .collect();
+
+    for y in h / 2..h {
+        // distancia a la que esta fila toca el suelo (la cámara está a media altura)
+        let p = y as f32 + 0.5 - sh / 2.0;
+        let row_dist = (0.5 * sh) / p;
+        let step = (ray_right - ray_left) * (row_dist / w as f32);
+        let mut world = cam.pos + ray_left * row_dist;
+        let ceil_y = h - 1 - y;
+        let sky_v = ceil_y as f32 / (sh / 2.0);
+
+        for (x, &su) in sky_u.iter().enumerate() {
+            let cx = world.x.floor() as i32;
+            let cy = world.y.floor() as i32;
+            let u = world.x - cx as f32;
+            let v = world.y - cy as f32;
+
+            let l = light.sample(world);
+            let floor = match Surfaces::texture_at(&surfaces.floor, &surfaces.floor_textures, cx, cy) {
+                Some(t) => t.sample(u, v) | 0xff00_0000,
+                None => floor_color,
+            };
+            fb.pixels[y * w + x] = fogged(fog, lit(floor, l), row_dist);
+            fb.pixels[ceil_y * w + x] = match Surfaces::texture_at(&surfaces.ceiling, &surfaces.ceiling_textures, cx, cy) {
+                Some(t) => fogged(fog, lit(t.sample(u, v) | 0xff00_0000, l), row_dist),
+                None => match &surfaces.sky {
+                    Some(sky) => sky.sample(su, sky_v) | 0xff00_0000,
+                    None => fogged(fog, lit(ceiling_color, l), row_dist),
+                },
+            };
+            world += step;
+        }
+    }
+}
+
+/// Dibuja suelo, techo y paredes texturizadas y devuelve z-buffer (distancia perpendicular por columna).
+/// La celda 3 (salida) se considera muro **solo** cuando quedan amigos (`state.coins > 0`).
+/// Las caras cruzadas en y se oscurecen con `SIDE_SHADE`, cada columna toma la luz del punto
+/// donde el rayo toca la pared y todo se mezcla con la niebla del nivel.
+/// Con alturas variables el rayo sigue de largo tras las paredes más bajas que la más alta del
+/// nivel, y cada pared más lejana se dibuja solo por encima de las que ya tapan la columna.
+/// Las paredes animadas muestran el cuadro del instante `t` (segundos).
+/// Las paredes que dejan ver a través no detienen el rayo: se guardan como franjas para
+/// componerlas de atrás hacia adelante junto con los sprites (ver `render_sprites`).
+pub fn render_scene(
+    fb: &mut Framebuffer,
+    cam: &Camera,
+    state: &LevelState,
+    assets: &SceneAssets,
+    light: &LightGrid,
+    t: f32,
+) -> SceneLayers {
+    let w = fb.width;
+    let sw = w as f32;
+    let sh = fb.height as f32;
+    let fog = assets.fog.as_ref();
+    render_floor_ceiling(fb, cam, &assets.surfaces, light, fog);
+
+    let mut z_buffer = vec![1e30f32; w];
+    let mut translucent = Vec::new();
+    let mut low = Vec::new();
+
+    for (x, z) in z_buffer.iter_mut().enumerate() {
+        let camera_x = 2.0 * x as f32 / sw - 1.0;
+        let ray_dir = vec2(
+            cam.dir.x + cam.plane.x * camera_x,
+            cam.dir.y + cam.plane.y * camera_x,
+        );
+
+        // fila de pantalla por encima de la cual todavía no se dibujó ninguna pared en esta columna
+        let mut clip = sh;
+
+        for GridStep { x: map_x, y: map_y, side, enter, leave, step_x, step_y } in GridWalk::new(cam.pos, ray_dir) {
+            let Some(cell) = state.map.get(map_x, map_y) else {
+                break;
+            };
+
+            // distancia perpendicular, coordenada u en la textura, textura, lado y altura de la cara
+            let mut opacity = None;
+            let face = if let Some(door) = state.door_at(map_x, map_y) {
+                // puertas: la hoja está en la mitad de la celda; si el rayo pasa por el hueco, sigue
+                door_hit(door, cam.pos, ray_dir, enter, leave).map(|(dist, u)| {
+                    let tex = assets.doors.get(&door.cell).unwrap_or(assets.walls.registry().get(0, t));
+                    let door_side = if door.axis == DoorAxis::AlongX { 1 } else { 0 };
+                    (dist, u, tex, door_side, 1.0)
+                })
+            } else if state.is_blocking(cell) {
+                let perp_wall_dist = if side == 0 {
+                    (map_x as f32 - cam.pos.x + (1 - step_x) as f32 / 2.0) / ray_dir.x
+                } else {
+                    (map_y as f32 - cam.pos.y + (1 - step_y) as f32 / 2.0) / ray_dir.y
+                };
+                // wallX: posición fraccional en la pared (0..1)
+                let mut wall_x = if side == 0 {
+                    cam.pos.y + perp_wall_dist * ray_dir.y
+                } else {
+                    cam.pos.x + perp_wall_dist * ray_dir.x
+                };
+                wall_x -= wall_x.floor();
+                let tex = assets.walls.for_face(map_x, map_y, Face::hit(side, step_x, step_y), t);
+                opacity = assets.walls.see_through(map_x, map_y);
+                Some((perp_wall_dist, wall_x, tex, side, assets.heights.at(map_x, map_y)))
+            } else {
+                None
+            };
+            let Some((perp_wall_dist, wall_x, tex, side, height)) = face else {
+                continue;
+            };
+
+            // el z-buffer de los sprites guarda la primera pared opaca que tapa el horizonte entero
+            if opacity.is_none() && perp_wall_dist > 0.0 && height >= 1.0 && *z == 1e30 {
+                *z = perp_wall_dist;
+            }
+
+            // line_h es el alto en pantalla de una unidad de pared; la base queda siempre en el suelo
+            let line_h = (sh / perp_wall_dist.max(0.0001)).round();
+            let draw_start = (-line_h / 2.0) + sh / 2.0 - (height - 1.0) * line_h;
+            let draw_end = (line_h / 2.0) + sh / 2.0;
+            // la textura se repite cada unidad de altura, alineada con el suelo
+            let v_offset = height.ceil() - height;
+
+            let shade = if side == 1 { SIDE_SHADE } else { 1.0 };
+            // luz justo delante de la cara (un poco hacia la cámara, para no muestrear dentro del muro)
+            let hit = cam.pos + ray_dir * perp_wall_dist - ray_dir.normalize() * 0.01;
+            let l = light.sample(hit) * shade;
+
+            // paredes por debajo de los ojos: se ve su cara superior hasta donde el rayo sale de la celda
+            let top = if height < 0.5 { sh / 2.0 + (0.5 - height) * sh / leave.max(0.0001) } else { draw_start };
+            let mut strip = WallStrip { x, dist: perp_wall_dist, y0: top.max(0.0) as usize, pixels: Vec::new() };
+            for y in strip.y0..draw_start.clamp(0.0, clip) as usize {
+                let dist = (0.5 - height) * sh / (y as f32 + 0.5 - sh / 2.0);
+                let world = cam.pos + ray_dir * dist;
+                let texel = tex.sample(world.x - world.x.floor(), world.y - world.y.floor());
+                strip.pixels.push(fogged(fog, lit(texel, light.sample(world)), dist));
+            }
+            for y in draw_start.max(0.0) as usize..draw_end.clamp(0.0, clip) as usize {
+                let mut v = (y as f32 + 0.5 - draw_start) / line_h + v_offset;
+                if v >= 1.0 {
+                    v = v.fract();
+                }
+                strip.pixels.push(fogged(fog, lit(tex.sample(wall_x, v), l), perp_wall_dist));
+            }
+
+            if let Some(opacity) = opacity {
+                // se compone después, de atrás hacia adelante; detrás puede haber de todo
+                strip.pixels.iter_mut().for_each(|p| *p = fade(*p, opacity));
+                translucent.push(strip);
+                continue;
+            }
+            strip.draw(fb);
+            clip = clip.min(top.max(0.0));
+            // una pared baja delante del z-buffer se vuelve a dibujar sobre los sprites que tapa
+            if perp_wall_dist < *z && !strip.pixels.is_empty() {
+                low.push(strip);
+            }
+
+            // nada más lejano puede asomar por encima de la pared más alta del nivel
+            if height >= assets.heights.max || clip <= 0.0 {
+                break;
+            }
+        }
+    }
+
+    SceneLayers { z_buffer, translucent, low }
+}
+
+/// Recorrido DDA de un rayo por las celdas de la cuadrícula, de la más cercana a la más lejana
+/// (sin fin: quien lo usa decide cuándo parar). Lo usan el raycaster y la línea de visión.
+pub struct GridWalk {
+    map_x: i32,
+    map_y: i32,
+    step_x: i32,
+    step_y: i32,
+    side_dist_x: f32,
+    side_dist_y: f32,
+    delta_dist_x: f32,
+    delta_dist_y: f32,
+}
+
+/// Celda a la que entra el rayo. Las distancias van en unidades de `dir` (perpendiculares al
+/// plano de cámara si `dir` es un rayo de cámara).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridStep {
+    pub x: i32,
+    pub y: i32,
+    /// 0 si entró cruzando una línea vertical (avanzó en x), 1 si avanzó en y.
+    pub side: i32,
+    /// Distancia a la que entra y a la que sale de la celda.
+    pub enter: f32,
+    pub leave: f32,
+    pub step_x: i32,
+    pub step_y: i32,
+}
+
+impl GridWalk {
+    pub fn new(pos: Vec2, dir: Vec2) -> Self {
+        let map_x = pos.x as i32;
+        let map_y = pos.y as i32;
+
+        let delta_dist_x = if dir.x == 0.0 { 1e30 } else { (1.0 / dir.x).abs() };
+        let delta_dist_y = if dir.y == 0.0 { 1e30 } else { (1.0 / dir.y).abs() };
+
+        let (step_x, side_dist_x) = if dir.x < 0.0 {
+            (-1, (pos.x - map_x as f32) * delta_dist_x)
+        } else {
+            (1, (map_x as f32 + 1.0 - pos.x) * delta_dist_x)
+        };
+        let (step_y, side_dist_y) = if dir.y < 0.0 {
+            (-1, (pos.y - map_y as f32) * delta_dist_y)
+        } else {
+            (1, (map_y as f32 + 1.0 - pos.y) * delta_dist_y)
+        };
+        Self { map_x, map_y, step_x, step_y, side_dist_x, side_dist_y, delta_dist_x, delta_dist_y }
+    }
+}
+
+impl Iterator for GridWalk {
+    type Item = GridStep;
+
+    fn next(&mut self) -> Option<GridStep> {
+        // distancia a la que el rayo entra en la próxima celda
+        let enter = self.side_dist_x.min(self.side_dist_y);
+        let side = if self.side_dist_x < self.side_dist_y {
+            self.side_dist_x += self.delta_dist_x;
+            self.map_x += self.step_x;
+            0
+        } else {
+            self.side_dist_y += self.delta_dist_y;
+            self.map_y += self.step_y;
+            1
+        };
+        let leave = self.side_dist_x.min(self.side_dist_y);
+        Some(GridStep { x: self.map_x, y: self.map_y, side, enter, leave, step_x: self.step_x, step_y: self.step_y })
+    }
+}
+
+/// Tramo vertical de una pared en una columna de pantalla, ya iluminado y con niebla.
+struct WallStrip {
+    x: usize,
+    /// Distancia perpendicular a la cámara (para ordenarlo con los sprites).
+    dist: f32,
+    /// Primera fila; `pixels` sigue hacia abajo.
+    y0: usize,
+    pixels: Vec<u32>,
+}
+
+impl WallStrip {
+    fn draw(&self, fb: &mut Framebuffer) {
+        for (k, &p) in self.pixels.iter().enumerate() {
+            let i = (self.y0 + k) * fb.width + self.x;
+            fb.pixels[i] = blend(fb.pixels[i], p);
+        }
+    }
+}
+
+/// Lo que deja `render_scene` para la pasada de sprites: la profundidad de la pared opaca más
+/// cercana por columna, las franjas de paredes translúcidas que faltan componer y las de paredes
+/// bajas, que no llegan al z-buffer pero tapan la parte de abajo de lo que está detrás.
+pub struct SceneLayers {
+    pub z_buffer: Vec<f32>,
+    translucent: Vec<WallStrip>,
+    low: Vec<WallStrip>,
+}
+
+/// Intersección de un rayo con la hoja de una puerta (a media celda), si ocurre entre `enter`
+/// y `leave` (distancias a las que el rayo entra y sale de la celda) y en la parte de la hoja que
+/// aún no se deslizó. Devuelve la distancia perpendicular y la coordenada u en la textura.
+fn door_hit(door: &Door, pos: Vec2, ray: Vec2, enter: f32, leave: f32) -> Option<(f32, f32)> {
+    let (dist, along) = match door.axis {
+        DoorAxis::AlongY if ray.x != 0.0 => {
+            let t = (door.x as f32 + 0.5 - pos.x) / ray.x;
+            (t, pos.y + t * ray.y - door.y as f32)
+        }
+        DoorAxis::AlongX if ray.y != 0.0 => {
+            let t = (door.y as f32 + 0.5 - pos.y) / ray.y;
+            (t, pos.x + t * ray.x - door.x as f32)
+        }
+        _ => return None,
+    };
+    if dist < enter || dist > leave || along < door.open {
+        return None;
+    }
+    Some((dist, along - door.open))
+}
+
+/// Sprite a dibujar en un cuadro: posición libre en el mundo (no atada a una celda) y apariencia.
+#[derive(Clone, Copy)]
+pub struct Billboard<'a> {
+    pub pos: Vec2,
+    /// Hacia dónde mira la entidad y si está caminando (eligen vista y animación del sprite).
+    pub facing: Vec2,
+    pub walking: bool,
+    pub sprite: &'a SpriteRender,
+}
+
+/// Sprites que pone el propio nivel: amigos y objetos sin recoger, enemigos y la salida, que
+/// solo se ve como sprite cuando ya no faltan amigos.
+pub fn level_billboards<'a>(state: &LevelState, assets: &'a SceneAssets) -> Vec<Billboard<'a>> {
+    let billboard = |pos: Vec2, sprite: &'a SpriteRender| Billboard { pos, facing: sprite.facing, walking: false, sprite };
+    let mut list: Vec<Billboard> = state
+        .pickups
+        .iter()
+        .filter_map(|p| {
+            let sprite = match p.kind {
+                PickupKind::Friend(cell) => assets.sprites.get(&cell),
+                PickupKind::Item(id) => assets.item_sprites.get(&id),
+            };
+            sprite.map(|s| billboard(p.pos, s))
+        })
+        .collect();
+    for enemy in &state.enemies {
+        if let Some(sprite) = assets.enemy_sprites.get(enemy.def) {
+            list.push(Billboard { pos: enemy.pos, facing: enemy.dir, walking: enemy.moving, sprite });
+        }
+    }
+    // la salida cerrada se ve como pared
+    if state.coins == 0
+        && let Some(sprite) = assets.sprites.get(&CELL_EXIT)
+    {
+        for y in 0..state.map.height as i32 {
+            for x in 0..state.map.width as i32 {
+                if state.map.get(x, y) == Some(CELL_EXIT) {
+                    list.push(billboard(vec2(x as f32 + 0.5, y as f32 + 0.5), sprite));
+                }
+            }
+        }
+    }
+    list
+}
+
+/// Dibuja los sprites y las paredes translúcidas que dejó `render_scene`, del más lejano al
+/// más cercano, para que se mezclen en orden (un amigo detrás de un vidrio se ve a través de él).
+/// Las paredes bajas se repiten encima de los sprites que quedan detrás: un amigo detrás de un
+/// seto asoma solo por encima.
+/// Cada columna del sprite se recorta por su cuenta con el z-buffer, así un amigo asoma a medias
+/// detrás de una esquina. Escala y bob vertical (sin() del tiempo `t`, en segundos) vienen de
+/// cada sprite.
+pub fn render_sprites(
+    fb: &mut Framebuffer,
+    cam: &Camera,
+    layers: &SceneLayers,
+    sprites: &[Billboard],
+    fog: Option<&Fog>,
+    light: &LightGrid,
+    t: f32,
+) {
+    enum Item<'a, 'b> {
+        Sprite(&'a Billboard<'b>),
+        Wall(&'a WallStrip),
+    }
+
+    let sw = fb.width as f32;
+    let sh = fb.height as f32;
+    let inv_det = 1.0 / (cam.plane.x * cam.dir.y - cam.dir.x * cam.plane.y);
+    // profundidad de cada sprite en el espacio de la cámara
+    let depth = |b: &Billboard| {
+        let rel = b.pos - cam.pos;
+        inv_det * (-cam.plane.y * rel.x + cam.plane.x * rel.y)
+    };
+
+    let mut order: Vec<(f32, Item)> = sprites
+        .iter()
+        .map(|b| (depth(b), Item::Sprite(b)))
+        .filter(|(d, _)| *d > 0.0)
+        .collect();
+    // las paredes bajas solo hace falta repetirlas si hay algún sprite detrás
+    let farthest = order.iter().map(|(d, _)| *d).fold(0.0, f32::max);
+    order.extend(layers.translucent.iter().map(|w| (w.dist, Item::Wall(w))));
+    order.extend(layers.low.iter().filter(|w| w.dist < farthest).map(|w| (w.dist, Item::Wall(w))));
+    order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (transform_y, item) in order {
+        let billboard = match item {
+            Item::Wall(strip) => {
+                strip.draw(fb);
+                continue;
+            }
+            Item::Sprite(b) => b,
+        };
+        let sprite = billboard.sprite;
+        let rel = billboard.pos - cam.pos;
+        let transform_x = inv_det * (cam.dir.y * rel.x - cam.dir.x * rel.y);
+
+        let screen_x = (sw / 2.0) * (1.0 + transform_x / transform_y);
+
+        let sprite_h = (sh / transform_y).abs() * sprite.scale;
+        let sprite_w = sprite_h;
+
+        let draw_start_y = if sprite.grounded {
+            // la base del sprite en la línea del suelo a esa distancia
+            sh / 2.0 + (sh / transform_y) / 2.0 - sprite_h
+        } else {
+            (sh / 2.0) - (sprite_h / 2.0)
+        };
+        let draw_start_x = screen_x - (sprite_w / 2.0);
+
+        // bob vertical para dar vida al sprite
+        let bob = (t * sprite.bob_speed).sin() * (sprite_h * sprite.bob_amount);
+        let dest = Rect::new(draw_start_x, draw_start_y + bob, sprite_w, sprite_h);
+
+        let l = light.sample(billboard.pos);
+        let tex = sprite.frame(cam.pos - billboard.pos, billboard.facing, billboard.walking, t);
+        let z_buffer = &layers.z_buffer;
+        blit_scaled(fb, tex, dest, |px| transform_y < z_buffer[px], |p| fade(fogged(fog, lit(p, l), transform_y), sprite.opacity));
+    }
+}
+
+/// Dibuja `tex` escalada al rectángulo destino (en píxeles), recortada al framebuffer y con alfa.
+/// Solo pinta las columnas para las que `visible` da `true`; `tint` transforma cada texel antes
+/// de mezclarlo (luz y niebla).
+fn blit_scaled(fb: &mut Framebuffer, tex: &TextureData, dest: Rect, visible: impl Fn(usize) -> bool, tint: impl Fn(u32) -> u32) {
+    if dest.w <= 0.0 || dest.h <= 0.0 {
+        return;
+    }
+    let x0 = dest.x.max(0.0) as usize;
+    let x1 = (dest.x + dest.w).clamp(0.0, fb.width as f32) as usize;
+    let y0 = dest.y.max(0.0) as usize;
+    let y1 = (dest.y + dest.h).clamp(0.0, fb.height as f32) as usize;
+    let columns: Vec<usize> = (x0..x1).filter(|&px| visible(px)).collect();
+    for py in y0..y1 {
+        let v = (py as f32 + 0.5 - dest.y) / dest.h;
+        for &px in &columns {
+            let u = (px as f32 + 0.5 - dest.x) / dest.w;
+            let i = py * fb.width + px;
+            fb.pixels[i] = blend(fb.pixels[i], tint(tex.sample(u, v)));
+        }
+    }
+}
+
+/// Renderiza un cuadro completo (suelo, techo, paredes y sprites) y devuelve el z-buffer.
+/// La luz se evalúa una vez por cuadro en el instante `t` (parpadeo, amigos recogidos).
+/// `extra` son sprites que no son parte del nivel (el fantasma) y se mezclan con los suyos.
+pub fn render_frame(
+    fb: &mut Framebuffer,
+    cam: &Camera,
+    state: &LevelState,
+    assets: &SceneAssets,
+    extra: &[Billboard],
+    t: f32,
+) -> Vec<f32> {
+    let light = assets.lights.evaluate(state, t);
+    let layers = render_scene(fb, cam, state, assets, &light, t);
+    let mut billboards = level_billboards(state, assets);
+    billboards.extend_from_slice(extra);
+    render_sprites(fb, cam, &layers, &billboards, assets.fog.as_ref(), &light, t);
+    layers.z_buffer
+}
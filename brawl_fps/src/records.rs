@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::nivel::ParseError;
+use crate::puntaje::RunResults;
+
+// Tabla de récords: los mejores puntajes y el mejor tiempo de cada nivel, con las iniciales
+// de quien los hizo. Se guarda como texto plano en el directorio de datos del usuario:
+//
+//   # Récords de Shrek Find and Rescue
+//   version = 1
+//
+//   [Nivel 1]
+//   puntaje = ABC 1234 45.30
+//   puntaje = XYZ 900 61.02
+//   tiempo = ABC 1234 45.30
+//
+// Cada entrada es `iniciales puntaje segundos`. La versión va primero para poder cambiar el
+// formato más adelante; un archivo de otra versión o dañado se ignora con un aviso (como las
+// texturas que no cargan) y se empieza con la tabla vacía.
+
+/// Versión del formato que se escribe (y la única que se sabe leer).
+pub const RECORDS_VERSION: u32 = 1;
+/// Puntajes que se guardan por nivel.
+pub const MAX_SCORES: usize = 5;
+/// Largo máximo de las iniciales.
+pub const MAX_INITIALS: usize = 3;
+
+/// Una partida ganada que entró en la tabla.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordEntry {
+    pub initials: String,
+    pub score: u32,
+    /// Segundos que tomó la partida.
+    pub time: f32,
+}
+
+/// Récords de un nivel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelRecords {
+    /// Mejores puntajes, de mayor a menor (a lo sumo `MAX_SCORES`).
+    pub scores: Vec<RecordEntry>,
+    /// Partida más rápida.
+    pub best_time: Option<RecordEntry>,
+}
+
+impl LevelRecords {
+    fn insert(&mut self, entry: RecordEntry) {
+        if self.best_time.as_ref().is_none_or(|b| entry.time < b.time) {
+            self.best_time = Some(entry.clone());
+        }
+        // a igual puntaje queda primero el más antiguo
+        let at = self.scores.iter().position(|e| entry.score > e.score).unwrap_or(self.scores.len());
+        self.scores.insert(at, entry);
+        self.scores.truncate(MAX_SCORES);
+    }
+}
+
+/// Récords de todos los niveles, por nombre de nivel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordTable {
+    levels: BTreeMap<String, LevelRecords>,
+}
+
+impl RecordTable {
+    /// Récords del nivel `level`, si tiene alguno.
+    pub fn level(&self, level: &str) -> Option<&LevelRecords> {
+        self.levels.get(level)
+    }
+
+    /// Si la partida `results` del nivel `level` entraría en la tabla (puntaje o tiempo).
+    pub fn qualifies(&self, level: &str, results: &RunResults) -> bool {
+        let Some(rec) = self.levels.get(level) else {
+            return true;
+        };
+        let by_score = rec.scores.len() < MAX_SCORES || rec.scores.last().is_some_and(|e| results.total > e.score);
+        let by_time = rec.best_time.as_ref().is_none_or(|b| results.time < b.time);
+        by_score || by_time
+    }
+
+    /// Anota la partida `results` del nivel `level` a nombre de `initials` (se normalizan a
+    /// mayúsculas y se recortan a `MAX_INITIALS`; si no queda ninguna, `AAA` como en los arcades).
+    pub fn submit(&mut self, level: &str, initials: &str, results: &RunResults) {
+        let mut initials: String = initials.chars().filter(|c| c.is_ascii_alphanumeric()).take(MAX_INITIALS).collect();
+        if initials.is_empty() {
+            initials = "AAA".to_string();
+        }
+        let entry = RecordEntry { initials: initials.to_ascii_uppercase(), score: results.total, time: results.time };
+        self.levels.entry(level.to_string()).or_default().insert(entry);
+    }
+
+    /// Lee la tabla de `path`. Si no existe, empieza vacía; si está dañada o es de otra
+    /// versión, avisa por stderr y también empieza vacía.
+    pub fn load(path: &Path) -> Self {
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                eprintln!("Warning: no se pudieron leer los récords de {}: {}. Empezando con la tabla vacía.", path.display(), e);
+                return Self::default();
+            }
+        };
+        match Self::parse(&src) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("Warning: récords dañados en {}: {}. Empezando con la tabla vacía.", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Escribe la tabla en `path`, creando los directorios que falten.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let err = |line: usize, message: String| ParseError { line, column: 1, message };
+        let mut table = Self::default();
+        let mut version = None;
+        let mut current: Option<String> = None;
+        for (i, raw) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if version.is_none() {
+                let v = line
+                    .strip_prefix("version")
+                    .and_then(|rest| rest.trim_start().strip_prefix('='))
+                    .and_then(|v| v.trim().parse::<u32>().ok())
+                    .ok_or_else(|| err(line_no, "se esperaba `version = <n>` al principio".to_string()))?;
+                if v != RECORDS_VERSION {
+                    return Err(err(line_no, format!("versión {} no soportada (se esperaba {})", v, RECORDS_VERSION)));
+                }
+                version = Some(v);
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                table.levels.entry(name.to_string()).or_default();
+                current = Some(name.to_string());
+                continue;
+            }
+            let Some(level) = &current else {
+                return Err(err(line_no, "entrada fuera de la sección de un nivel".to_string()));
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(err(line_no, format!("se esperaba `clave = valor`, se encontró `{}`", line)));
+            };
+            let entry = parse_entry(value.trim()).ok_or_else(|| err(line_no, format!("entrada inválida `{}`", value.trim())))?;
+            let rec = table.levels.get_mut(level).expect("sección creada al leer su encabezado");
+            match key.trim() {
+                "puntaje" => rec.scores.push(entry),
+                "tiempo" => rec.best_time = Some(entry),
+                other => return Err(err(line_no, format!("clave desconocida `{}`", other))),
+            }
+        }
+        if version.is_none() {
+            return Err(err(src.lines().count() + 1, "falta `version = <n>`".to_string()));
+        }
+        for rec in table.levels.values_mut() {
+            // el orden estable respeta el del archivo entre puntajes iguales
+            rec.scores.sort_by_key(|e| std::cmp::Reverse(e.score));
+            rec.scores.truncate(MAX_SCORES);
+        }
+        Ok(table)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("# Récords de Shrek Find and Rescue\nversion = {}\n", RECORDS_VERSION);
+        for (name, rec) in &self.levels {
+            out += &format!("\n[{}]\n", name);
+            for e in &rec.scores {
+                out += &format!("puntaje = {}\n", format_entry(e));
+            }
+            if let Some(e) = &rec.best_time {
+                out += &format!("tiempo = {}\n", format_entry(e));
+            }
+        }
+        out
+    }
+}
+
+fn format_entry(e: &RecordEntry) -> String {
+    format!("{} {} {:.2}", e.initials, e.score, e.time)
+}
+
+/// `iniciales puntaje segundos`; `None` si no tiene esa forma.
+fn parse_entry(value: &str) -> Option<RecordEntry> {
+    let mut parts = value.split_whitespace();
+    let initials = parts.next()?;
+    let score = parts.next()?.parse::<u32>().ok()?;
+    let time = parts.next()?.parse::<f32>().ok()?;
+    let valid_initials = initials.len() <= MAX_INITIALS && initials.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if parts.next().is_some() || !valid_initials || !time.is_finite() || time < 0.0 {
+        return None;
+    }
+    Some(RecordEntry { initials: initials.to_string(), score, time })
+}
+
+/// Archivo de récords dentro del directorio de datos del usuario (`None` si no se puede
+/// determinar, p. ej. sin `HOME`).
+pub fn records_path() -> Option<PathBuf> {
+    data_dir().map(|d| d.join("shrek_find_and_rescue").join("records.txt"))
+}
+
+/// Archivo con la mejor partida del nivel `level` (su fantasma), junto al de récords.
+pub fn ghost_path(level: &str) -> Option<PathBuf> {
+    let file: String = level.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    data_dir().map(|d| d.join("shrek_find_and_rescue").join("fantasmas").join(format!("{}.txt", file)))
+}
+
+fn data_dir() -> Option<PathBuf> {
+    let env = |key: &str| std::env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+    if cfg!(windows) {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|h| h.join("Library").join("Application Support"))
+    } else {
+        env("XDG_DATA_HOME").or_else(|| env("HOME").map(|h| h.join(".local").join("share")))
+    }
+}
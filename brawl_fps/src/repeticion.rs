@@ -0,0 +1,243 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::nivel::{LevelDef, ParseError};
+use crate::raycaster::Camera;
+use crate::simulacion::{update, Game, InputFrame, TICK_DT, TICK_RATE};
+
+// Grabación y repetición de partidas. Como la simulación avanza a paso fijo y solo depende de la
+// entrada de cada paso (ver `simulacion.rs`), alcanza con guardar la `InputFrame` de cada paso
+// para reproducir la partida exacta: mismo recorrido de la cámara, mismas recolecciones y mismo
+// tiempo final. Las teclas que no tocan la simulación (pausa, M para el mouse) no se graban.
+//
+// Formato del archivo (texto plano):
+//
+//   # Repetición de Shrek Find and Rescue
+//   version = 1
+//   nivel = Nivel 1
+//   pasos_por_segundo = 60
+//   [pasos]
+//   30 F---- 0
+//   1 F---U 0.0125
+//   12 -B-R- -0.003
+//
+// Cada línea de `[pasos]` es `<repeticiones> <teclas> <giro>`: la misma entrada durante tantos
+// pasos seguidos; las teclas son F (adelante), B (atrás), L y R (de costado) y U (usar), con `-`
+// si no está apretada; el giro está en radianes y se escribe con todos sus dígitos, así se lee
+// exactamente el mismo número.
+//
+// La partida ganada más rápida de cada nivel se guarda como "fantasma" (ver
+// `records::ghost_path`): al jugar con el fantasma activado, `Ghost` la vuelve a correr paso a
+// paso junto a la del jugador, para que pueda competir contra su mejor tiempo.
+
+/// Versión del formato que se escribe (y la única que se sabe leer).
+pub const REPLAY_VERSION: u32 = 1;
+/// Pasos como mucho en una repetición (una hora de juego); un archivo con más se rechaza en vez
+/// de reservar memoria para todos.
+pub const MAX_REPLAY_TICKS: usize = 3600 * TICK_RATE as usize;
+
+/// Error al cargar una repetición desde disco (lectura o parseo).
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(String, std::io::Error),
+    Parse(String, ParseError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(path, e) => write!(f, "no se pudo leer {}: {}", path, e),
+            ReplayError::Parse(path, e) => write!(f, "{}: {}", path, e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Entrada de una partida, paso a paso.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// Nombre del nivel (`LevelDef::name`) en el que se grabó.
+    pub level: String,
+    pub inputs: Vec<InputFrame>,
+}
+
+impl Recording {
+    pub fn new(level: &str) -> Self {
+        Self { level: level.to_string(), inputs: Vec::new() }
+    }
+
+    /// Segundos de juego que cubre la grabación.
+    pub fn duration(&self) -> f32 {
+        self.inputs.len() as f32 * TICK_DT
+    }
+
+    pub fn load(path: &str) -> Result<Self, ReplayError> {
+        let src = std::fs::read_to_string(path).map_err(|e| ReplayError::Io(path.to_string(), e))?;
+        Self::parse(&src).map_err(|e| ReplayError::Parse(path.to_string(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())
+    }
+
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let err = |line: usize, message: String| ParseError { line, column: 1, message };
+        let mut version = None;
+        let mut level = None;
+        let mut tick_rate = None;
+        let mut in_steps = false;
+        let mut inputs = Vec::new();
+        let mut last_line = 0;
+        for (i, raw) in src.lines().enumerate() {
+            let line_no = i + 1;
+            last_line = line_no;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if in_steps {
+                let (count, input) = parse_step(line).ok_or_else(|| err(line_no, format!("paso inválido `{}`", line)))?;
+                if count > MAX_REPLAY_TICKS - inputs.len() {
+                    return Err(err(line_no, format!("la repetición pasa de {} pasos", MAX_REPLAY_TICKS)));
+                }
+                inputs.extend(std::iter::repeat_n(input, count));
+                continue;
+            }
+            if line == "[pasos]" {
+                in_steps = true;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(err(line_no, format!("se esperaba `clave = valor` o `[pasos]`, se encontró `{}`", line)));
+            };
+            let value = value.trim();
+            let number = || value.parse::<u32>().map_err(|_| err(line_no, format!("número inválido `{}`", value)));
+            match key.trim() {
+                "version" => {
+                    let v = number()?;
+                    if v != REPLAY_VERSION {
+                        return Err(err(line_no, format!("versión {} no soportada (se esperaba {})", v, REPLAY_VERSION)));
+                    }
+                    version = Some(v);
+                }
+                "nivel" => level = Some(value.to_string()),
+                "pasos_por_segundo" => {
+                    let rate = number()?;
+                    if rate != TICK_RATE {
+                        return Err(err(line_no, format!("grabada a {} pasos por segundo, el juego usa {}", rate, TICK_RATE)));
+                    }
+                    tick_rate = Some(rate);
+                }
+                other => return Err(err(line_no, format!("clave desconocida `{}`", other))),
+            }
+        }
+        let missing = |what: &str| err(last_line + 1, format!("falta `{}`", what));
+        if version.is_none() {
+            return Err(missing("version"));
+        }
+        if tick_rate.is_none() {
+            return Err(missing("pasos_por_segundo"));
+        }
+        let level = level.ok_or_else(|| missing("nivel"))?;
+        if !in_steps {
+            return Err(missing("[pasos]"));
+        }
+        Ok(Self { level, inputs })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "# Repetición de Shrek Find and Rescue\nversion = {}\nnivel = {}\npasos_por_segundo = {}\n[pasos]\n",
+            REPLAY_VERSION, self.level, TICK_RATE
+        );
+        // pasos iguales seguidos van en una sola línea
+        let mut rest = self.inputs.as_slice();
+        while let Some(first) = rest.first() {
+            let count = rest.iter().take_while(|i| *i == first).count();
+            out += &format!("{} {} {}\n", count, keys(first), first.turn);
+            rest = &rest[count..];
+        }
+        out
+    }
+}
+
+/// Juega la grabación entera sin ventana y devuelve cómo quedó la partida.
+pub fn replay<'a>(def: &'a LevelDef, recording: &Recording) -> Game<'a> {
+    let mut game = Game::new(def);
+    for input in &recording.inputs {
+        update(&mut game, input, TICK_DT);
+    }
+    game
+}
+
+/// Una partida grabada ya jugada de antemano: la cámara en cada paso, para mostrarla junto a la
+/// partida actual como un fantasma.
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    /// Cámara al empezar (`path[0]`) y después de cada paso.
+    pub path: Vec<Camera>,
+    /// Si terminó ganando la partida (el tiempo contra el que se corre).
+    pub won: bool,
+}
+
+impl Ghost {
+    pub fn new(def: &LevelDef, recording: &Recording) -> Self {
+        let mut game = Game::new(def);
+        let mut path = Vec::with_capacity(recording.inputs.len() + 1);
+        path.push(game.cam);
+        for input in &recording.inputs {
+            update(&mut game, input, TICK_DT);
+            path.push(game.cam);
+        }
+        Self { path, won: game.level.won }
+    }
+
+    /// Segundos de juego que dura el recorrido.
+    pub fn duration(&self) -> f32 {
+        (self.path.len() - 1) as f32 * TICK_DT
+    }
+
+    /// Cámara del fantasma tras `tick` pasos, a una fracción `alpha` del camino desde el paso
+    /// anterior (como `Game::interpolated_cam`). Al acabarse el recorrido se queda donde terminó.
+    pub fn cam_at(&self, tick: usize, alpha: f32) -> Camera {
+        let last = self.path.len() - 1;
+        self.path[tick.saturating_sub(1).min(last)].lerp(&self.path[tick.min(last)], alpha)
+    }
+
+    /// Si el fantasma se movió en el paso `tick` (para la animación de caminar).
+    pub fn walking_at(&self, tick: usize) -> bool {
+        (1..self.path.len()).contains(&tick) && self.path[tick - 1].pos != self.path[tick].pos
+    }
+}
+
+const KEYS: [char; 5] = ['F', 'B', 'L', 'R', 'U'];
+
+fn keys(input: &InputFrame) -> String {
+    let held = [input.forward, input.back, input.left, input.right, input.use_door];
+    KEYS.iter().zip(held).map(|(&k, on)| if on { k } else { '-' }).collect()
+}
+
+/// `<repeticiones> <teclas> <giro>`; `None` si no tiene esa forma.
+fn parse_step(line: &str) -> Option<(usize, InputFrame)> {
+    let mut parts = line.split_whitespace();
+    let count = parts.next()?.parse::<usize>().ok().filter(|&n| n > 0)?;
+    let flags: Vec<char> = parts.next()?.chars().collect();
+    let turn = parts.next()?.parse::<f32>().ok().filter(|t| t.is_finite())?;
+    if parts.next().is_some() || flags.len() != KEYS.len() {
+        return None;
+    }
+    let mut held = [false; 5];
+    for (i, (&f, &k)) in flags.iter().zip(KEYS.iter()).enumerate() {
+        match f {
+            '-' => {}
+            _ if f == k => held[i] = true,
+            _ => return None,
+        }
+    }
+    let [forward, back, left, right, use_door] = held;
+    Some((count, InputFrame { forward, back, left, right, turn, use_door }))
+}
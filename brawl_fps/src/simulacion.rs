@@ -0,0 +1,209 @@
+use macroquad::prelude::*;
+
+use crate::enemigos::update_enemies;
+use crate::nivel::{DoorLock, DoorUse, LevelDef, LevelState, PickupKind};
+use crate::raycaster::Camera;
+
+// Simulación de una partida a paso fijo: `update` avanza el juego `TICK_DT` segundos a partir de
+// la entrada de ese paso, sin leer el teclado, el reloj ni dibujar nada. El bucle de `juego.rs`
+// acumula el tiempo real y llama a `update` tantas veces como pasos quepan; al dibujar interpola
+// la cámara entre el paso anterior y el actual, así el movimiento es suave a cualquier FPS y la
+// partida depende solo de la secuencia de entradas.
+//
+// Sin ventana, `Game` se puede manejar desde pruebas o herramientas: se le dan entradas con
+// `step` / `run_for` y se mira cómo quedó (amigos que faltan, salida abierta, `won`).
+
+/// Pasos de simulación por segundo.
+pub const TICK_RATE: u32 = 60;
+/// Duración de un paso de simulación (segundos).
+pub const TICK_DT: f32 = 1.0 / TICK_RATE as f32;
+/// Velocidad del jugador (celdas por segundo).
+pub const MOVE_SPEED: f32 = 2.5;
+/// Margen para considerar que el jugador solo roza una pared (errores de redondeo al deslizarse).
+const CONTACT_SLACK: f32 = 1e-4;
+
+/// Entrada del jugador durante un paso de simulación.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InputFrame {
+    pub forward: bool,
+    pub back: bool,
+    /// Desplazamiento lateral (strafe).
+    pub left: bool,
+    pub right: bool,
+    /// Giro de la cámara en radianes (positivo = a la derecha).
+    pub turn: f32,
+    /// Se pulsó la tecla de usar (abrir/cerrar puertas) en este paso.
+    pub use_door: bool,
+}
+
+/// Algo que pasó durante un paso y que el bucle de juego tiene que mostrar o hacer sonar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    Picked(PickupKind),
+    /// Un enemigo (índice en `LevelDef::enemies`) alcanzó al jugador.
+    EnemyHit(usize),
+    /// El jugador se lastimó en un peligro (id de `peligro`).
+    HazardHit(i32),
+    DoorLocked(DoorLock),
+    Won,
+    Lost,
+}
+
+/// Estado completo de una partida: el del nivel más la cámara del jugador.
+#[derive(Debug, Clone)]
+pub struct Game<'a> {
+    pub def: &'a LevelDef,
+    pub level: LevelState,
+    pub cam: Camera,
+    /// Cámara al terminar el paso anterior, para interpolar al dibujar.
+    pub prev_cam: Camera,
+}
+
+impl<'a> Game<'a> {
+    /// Partida nueva en el nivel `def`, con el jugador en el spawn.
+    pub fn new(def: &'a LevelDef) -> Self {
+        let cam = Camera::new(def.spawn, def.dir);
+        Self { def, level: LevelState::new(def), cam, prev_cam: cam }
+    }
+
+    /// Si la partida terminó (ganada o perdida).
+    pub fn finished(&self) -> bool {
+        self.level.won || self.level.lost
+    }
+
+    /// Si ya se encontraron todos los amigos y la salida deja pasar.
+    pub fn exit_unlocked(&self) -> bool {
+        self.level.coins == 0
+    }
+
+    /// Avanza un paso de simulación con la entrada `input`.
+    pub fn step(&mut self, input: &InputFrame) -> Vec<GameEvent> {
+        update(self, input, TICK_DT)
+    }
+
+    /// Avanza `secs` segundos (redondeados a pasos enteros) manteniendo la misma entrada, o hasta
+    /// que termine la partida. Devuelve los eventos de todos los pasos.
+    pub fn run_for(&mut self, secs: f32, input: &InputFrame) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        for _ in 0..(secs / TICK_DT).round() as u32 {
+            if self.finished() {
+                break;
+            }
+            events.extend(self.step(input));
+        }
+        events
+    }
+
+    /// Cámara para dibujar a una fracción `alpha` (0..1) del camino entre el paso anterior y el actual.
+    pub fn interpolated_cam(&self, alpha: f32) -> Camera {
+        self.prev_cam.lerp(&self.cam, alpha)
+    }
+
+    /// Tiempo de juego para las animaciones del mismo cuadro que `interpolated_cam(alpha)`: el
+    /// reloj de la partida, que no corre en pausa, entre el paso anterior y el actual.
+    pub fn interpolated_time(&self, alpha: f32) -> f32 {
+        (self.level.elapsed - (1.0 - alpha) * TICK_DT).max(0.0)
+    }
+}
+
+/// Avanza la partida `dt` segundos con la entrada `input`. Devuelve lo que pasó en el paso.
+/// Una partida terminada ya no cambia.
+pub fn update(game: &mut Game, input: &InputFrame, dt: f32) -> Vec<GameEvent> {
+    let mut events = Vec::new();
+    game.prev_cam = game.cam;
+    if game.finished() {
+        return events;
+    }
+    let def = game.def;
+    let state = &mut game.level;
+    let cam = &mut game.cam;
+
+    cam.rotate(input.turn);
+
+    let move_step = MOVE_SPEED * state.speed_factor() * dt;
+    let dirc = cam.dir;
+    // strafing: derecha = (-dir.y, dir.x)
+    let right = vec2(-cam.dir.y, cam.dir.x);
+    if input.forward {
+        try_move(state, cam, dirc * move_step, def.player_radius);
+    }
+    if input.back {
+        try_move(state, cam, -dirc * move_step, def.player_radius);
+    }
+    if input.left {
+        try_move(state, cam, -right * move_step, def.player_radius);
+    }
+    if input.right {
+        try_move(state, cam, right * move_step, def.player_radius);
+    }
+
+    if input.use_door
+        && let DoorUse::Locked(lock) = state.use_door(def, cam.pos, cam.dir)
+    {
+        events.push(GameEvent::DoorLocked(lock));
+    }
+    state.update_doors(dt);
+    state.update_effects(dt);
+
+    if let Some(i) = update_enemies(state, def, cam.pos, dt) {
+        events.push(GameEvent::EnemyHit(i));
+    }
+    if let Some(hazard) = state.touch_hazard(def, cam.pos) {
+        events.push(GameEvent::HazardHit(hazard.id));
+    }
+    if state.lost {
+        events.push(GameEvent::Lost);
+        return events;
+    }
+
+    // recolección de amigos y objetos, y condición de salida
+    events.extend(state.collect_near(def, cam.pos).into_iter().map(GameEvent::Picked));
+    if state.reach_exit(cam.pos) {
+        events.push(GameEvent::Won);
+    }
+    events
+}
+
+/// Mueve la cámara `delta` tratando al jugador como un círculo de radio `radius`, un eje por vez:
+/// contra una pared se desliza por ella en vez de frenar en seco, y nunca se acerca a menos de
+/// `radius` de una celda que bloquea (la salida `3` solo mientras falten amigos, las puertas hasta
+/// que están casi abiertas del todo).
+fn try_move(state: &LevelState, cam: &mut Camera, delta: Vec2, radius: f32) {
+    cam.pos.x = move_along(state, cam.pos, delta.x, radius, true);
+    cam.pos.y = move_along(state, cam.pos, delta.y, radius, false);
+}
+
+/// Nueva coordenada de `pos` sobre un eje (x si `x_axis`, si no y) al avanzar `delta` por él,
+/// frenando donde el círculo toca la primera celda que bloquea.
+fn move_along(state: &LevelState, pos: Vec2, delta: f32, radius: f32, x_axis: bool) -> f32 {
+    // `along`: coordenada sobre el eje del movimiento; `across`: la otra
+    let (along, across) = if x_axis { (pos.x, pos.y) } else { (pos.y, pos.x) };
+    if delta == 0.0 {
+        return along;
+    }
+    let blocks = |a: i32, b: i32| if x_axis { state.blocks_at(a, b) } else { state.blocks_at(b, a) };
+    let mut target = along + delta;
+    let first = (along.min(target) - radius).floor() as i32;
+    let last = (along.max(target) + radius).floor() as i32;
+    for b in (across - radius).floor() as i32..=(across + radius).floor() as i32 {
+        // en las filas de al lado el círculo alcanza menos (las esquinas se redondean); las que
+        // apenas roza, como la pared por la que se desliza, no lo frenan
+        let gap = (b as f32 - across).max(across - (b + 1) as f32).max(0.0);
+        if gap >= radius - CONTACT_SLACK {
+            continue;
+        }
+        let reach = (radius * radius - gap * gap).sqrt();
+        for a in first..=last {
+            if !blocks(a, b) {
+                continue;
+            }
+            if delta > 0.0 && a as f32 >= along {
+                target = target.min(a as f32 - reach);
+            } else if delta < 0.0 && (a + 1) as f32 <= along {
+                target = target.max((a + 1) as f32 + reach);
+            }
+        }
+    }
+    // si ya estaba encimado a una pared, no lo empuja hacia atrás
+    if delta > 0.0 { target.max(along) } else { target.min(along) }
+}
@@ -0,0 +1,27 @@
+// Ayudas compartidas por las pruebas de integración: el mapa de prueba de tests/data/prueba.txt,
+// tal cual o retocado. Cada archivo de pruebas usa solo una parte.
+#![allow(dead_code)]
+
+use brawl_fps::nivel::LevelDef;
+
+/// Texto del mapa de prueba, para retocarlo o parsearlo a mano.
+pub fn source() -> String {
+    std::fs::read_to_string("tests/data/prueba.txt").expect("mapa de prueba")
+}
+
+/// El mapa de prueba con `extra` al principio y cada par de `replace` aplicado (para meter
+/// puertas, mover la salida o cambiar una línea).
+pub fn level(extra: &str, replace: &[(&str, &str)]) -> LevelDef {
+    let mut src = source();
+    for (from, to) in replace {
+        assert!(src.contains(from), "`{}` no está en el mapa", from);
+        src = src.replace(from, to);
+    }
+    LevelDef::parse(&format!("{}\n{}", extra, src)).expect("mapa de prueba válido")
+}
+
+/// El mapa de prueba con un peligro que quita `damage` en (3, 6), en el pasillo de abajo.
+pub fn hazard_level(damage: i32) -> LevelDef {
+    let hazards = "[peligros]\n00000000\n00000000\n00000000\n00000000\n00000000\n00000000\n00010000\n00000000\n";
+    LevelDef::parse(&format!("peligro 1 = dano={}\n{}\n{}", damage, source(), hazards)).expect("mapa de prueba válido")
+}
@@ -0,0 +1,132 @@
+// Pruebas de los enemigos sobre el mapa de prueba: rondas, persecución, línea de visión y
+// caminos alrededor de puertas, todo con `Game` y sin ventana.
+
+use brawl_fps::enemigos::{line_of_sight, EnemyMode, GIVE_UP_SECS};
+use brawl_fps::nivel::LevelState;
+use brawl_fps::raycaster::Camera;
+use brawl_fps::simulacion::{Game, GameEvent, InputFrame};
+use macroquad::math::{vec2, Vec2};
+
+mod common;
+use common::level;
+
+/// Puerta en (1, 5), entre el pasillo de la izquierda y el de abajo.
+const DOOR: (&str, &str) = ("11010001", "15010001");
+
+fn cell_of(p: Vec2) -> (i32, i32) {
+    (p.x.floor() as i32, p.y.floor() as i32)
+}
+
+fn idle() -> InputFrame {
+    InputFrame::default()
+}
+
+#[test]
+fn enemy_walks_its_route_back_and_forth() {
+    // ronda por el pasillo de arriba, con vista corta para que no vea al jugador
+    let def = level("enemigo = sprite=burro ruta=1,1;4,1 vision=2", &[]);
+    let mut game = Game::new(&def);
+    assert_eq!(game.level.enemies[0].pos, vec2(1.5, 1.5));
+
+    game.run_for(1.0, &idle());
+    let enemy = &game.level.enemies[0];
+    assert_eq!(enemy.mode, EnemyMode::Patrol);
+    assert!(enemy.moving);
+    assert!((enemy.pos - vec2(1.5 + def.enemies[0].speed, 1.5)).length() < 1e-3, "{:?}", enemy.pos);
+
+    // llega a (4, 1) a los 2 s y vuelve hacia el principio de la ronda
+    game.run_for(1.5, &idle());
+    let enemy = &game.level.enemies[0];
+    assert_eq!(enemy.mode, EnemyMode::Patrol);
+    assert!(enemy.dir.x < -0.99, "{:?}", enemy.dir);
+    assert!(enemy.pos.x < 4.0 && enemy.pos.y == 1.5, "{:?}", enemy.pos);
+}
+
+#[test]
+fn enemy_chases_the_player_once_it_sees_them() {
+    // de (6, 6) a (6, 3), con el jugador al otro lado del pasillo de abajo, a 5 celdas
+    let route = "enemigo = sprite=burro ruta=6,6;6,3 dano=30";
+    let def = level(&format!("{} vision=4", route), &[]);
+    let mut game = Game::new(&def);
+    game.run_for(0.5, &idle());
+    assert_eq!(game.level.enemies[0].mode, EnemyMode::Patrol, "no lo ve tan lejos");
+    assert!(game.level.enemies[0].dir.y < -0.99, "sigue la ronda hacia arriba");
+
+    let def = level(route, &[]);
+    let mut game = Game::new(&def);
+    game.step(&idle());
+    let enemy = &game.level.enemies[0];
+    assert_eq!(enemy.mode, EnemyMode::Chase);
+    assert!(enemy.dir.x < -0.99, "va derecho hacia el jugador: {:?}", enemy.dir);
+
+    // 4.55 celdas hasta tocarlo, a 1.3 veces su velocidad de ronda: un solo golpe en 2.5 s
+    let events = game.run_for(2.5, &idle());
+    assert_eq!(events, [GameEvent::EnemyHit(0)]);
+    assert_eq!(game.level.health, 70);
+}
+
+#[test]
+fn enemy_gives_up_after_losing_sight_of_the_player() {
+    let def = level("enemigo = sprite=burro ruta=6,6;6,3 vision=3", &[]);
+    let mut game = Game::new(&def);
+    game.cam = Camera::new(vec2(4.5, 6.5), vec2(1.0, 0.0));
+    game.step(&idle());
+    assert_eq!(game.level.enemies[0].mode, EnemyMode::Chase);
+
+    // el jugador desaparece lejos: el enemigo va a donde lo vio y espera ahí
+    game.cam = Camera::new(vec2(1.5, 1.5), vec2(1.0, 0.0));
+    game.run_for(GIVE_UP_SECS - 0.1, &idle());
+    let enemy = &game.level.enemies[0];
+    assert_eq!(enemy.mode, EnemyMode::Chase);
+    assert!((enemy.pos - vec2(4.5, 6.5)).length() <= 0.05, "{:?}", enemy.pos);
+    assert!(!enemy.moving);
+
+    game.run_for(0.2, &idle());
+    let enemy = &game.level.enemies[0];
+    assert_eq!(enemy.mode, EnemyMode::Patrol);
+    assert!(enemy.moving && enemy.dir.x > 0.99, "vuelve a su ronda: {:?}", enemy.dir);
+}
+
+#[test]
+fn walls_closed_doors_and_the_locked_exit_block_line_of_sight() {
+    // la salida pasa a (4, 1), entre dos celdas libres
+    let def = level("puerta 5 = textura=castillo", &[DOOR, ("10000031", "10003001"), ("20000033", "20003003")]);
+    let mut state = LevelState::new(&def);
+
+    assert!(line_of_sight(&state, vec2(1.5, 6.5), vec2(6.5, 6.5)));
+    assert!(!line_of_sight(&state, vec2(2.5, 3.5), vec2(4.5, 3.5)), "pared en (3, 3)");
+
+    assert!(!line_of_sight(&state, vec2(1.5, 6.5), vec2(1.5, 3.5)), "puerta cerrada");
+    state.doors[0].open = 1.0;
+    assert!(line_of_sight(&state, vec2(1.5, 6.5), vec2(1.5, 3.5)), "puerta abierta");
+
+    assert!(!line_of_sight(&state, vec2(3.5, 1.5), vec2(5.5, 1.5)), "salida cerrada");
+    state.coins = 0;
+    assert!(line_of_sight(&state, vec2(3.5, 1.5), vec2(5.5, 1.5)), "salida abierta");
+}
+
+#[test]
+fn enemy_takes_another_way_when_a_door_closes_in_front_of_it() {
+    let def = level("puerta 5 = textura=castillo\nenemigo = sprite=burro ruta=1,3;1,6 vision=1", &[DOOR]);
+    let mut game = Game::new(&def);
+    game.cam = Camera::new(vec2(6.5, 3.5), vec2(0.0, 1.0));
+    game.level.doors[0].opening = true;
+    game.level.doors[0].open = 1.0;
+
+    // con la puerta abierta va derecho hacia abajo
+    game.step(&idle());
+    assert!(game.level.enemies[0].dir.y > 0.99, "{:?}", game.level.enemies[0].dir);
+
+    // se cierra: la rodea por (2, 4), (2, 5) y (2, 6) sin meterse en ella
+    game.level.doors[0].opening = false;
+    game.level.doors[0].open = 0.0;
+    let mut cells = Vec::new();
+    for _ in 0..240 {
+        game.step(&idle());
+        let cell = cell_of(game.level.enemies[0].pos);
+        if cells.last() != Some(&cell) {
+            cells.push(cell);
+        }
+    }
+    assert_eq!(cells[..6], [(1, 3), (1, 4), (2, 4), (2, 5), (2, 6), (1, 6)]);
+}
@@ -0,0 +1,388 @@
+// Pruebas de regresión del raycaster: renderiza cuadros fijos fuera de pantalla y los compara
+// con imágenes de referencia en tests/golden/. Para regenerarlas tras un cambio intencional:
+//
+//   UPDATE_GOLDEN=1 cargo test --test golden
+//
+// Si una comparación falla, la imagen obtenida se guarda en target/golden-actual/.
+
+use std::collections::HashMap;
+
+use brawl_fps::nivel::{LevelDef, LevelState, GHOST_OPACITY};
+use brawl_fps::raycaster::{pack_rgba, render_frame, Billboard, Camera, Framebuffer, SceneAssets, TextureData};
+use macroquad::math::{vec2, Vec2};
+
+mod common;
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+/// Diferencia máxima por canal (0..255) para considerar dos píxeles iguales.
+const CHANNEL_TOLERANCE: u32 = 8;
+/// Fracción máxima de píxeles distintos antes de fallar.
+const MAX_DIFF_RATIO: f32 = 0.002;
+
+/// Tablero de ajedrez de 16x16 con dos colores, para que se note el muestreo de textura.
+fn checker(a: (u8, u8, u8), b: (u8, u8, u8)) -> TextureData {
+    let mut pixels = Vec::with_capacity(16 * 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            let (r, g, bl) = if (x / 4 + y / 4) % 2 == 0 { a } else { b };
+            pixels.push(pack_rgba(r, g, bl, 255));
+        }
+    }
+    TextureData { width: 16, height: 16, pixels }
+}
+
+/// Círculo opaco sobre fondo transparente (sprite con alfa).
+fn disc(r: u8, g: u8, b: u8) -> TextureData {
+    let mut pixels = Vec::with_capacity(16 * 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            let dx = x as f32 - 7.5;
+            let dy = y as f32 - 7.5;
+            let inside = dx * dx + dy * dy <= 7.5 * 7.5;
+            pixels.push(if inside { pack_rgba(r, g, b, 255) } else { 0 });
+        }
+    }
+    TextureData { width: 16, height: 16, pixels }
+}
+
+/// Reja: barrotes opacos con huecos transparentes.
+fn bars() -> TextureData {
+    let mut pixels = Vec::with_capacity(16 * 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            let solid = x % 4 == 0 || !(2..14).contains(&y);
+            pixels.push(if solid { pack_rgba(60, 60, 70, 255) } else { 0 });
+        }
+    }
+    TextureData { width: 16, height: 16, pixels }
+}
+
+/// Degradado horizontal (panorámica de cielo): se nota hacia dónde mira la cámara.
+fn gradient() -> TextureData {
+    let mut pixels = Vec::with_capacity(64 * 8);
+    for _y in 0..8 {
+        for x in 0..64 {
+            pixels.push(pack_rgba((x * 4) as u8, 120, 255 - (x * 4) as u8, 255));
+        }
+    }
+    TextureData { width: 64, height: 8, pixels }
+}
+
+/// Atlas con los cuadros uno al lado del otro.
+fn atlas(frames: &[TextureData]) -> TextureData {
+    let (w, h) = (frames[0].width, frames[0].height);
+    let mut pixels = Vec::with_capacity(w * h * frames.len());
+    for y in 0..h {
+        for f in frames {
+            pixels.extend_from_slice(&f.pixels[y * w..(y + 1) * w]);
+        }
+    }
+    TextureData { width: w * frames.len(), height: h, pixels }
+}
+
+/// Texturas procedurales con los nombres que usa tests/data/prueba.txt.
+fn test_textures() -> HashMap<String, TextureData> {
+    let mut textures = HashMap::new();
+    textures.insert("planicie".to_string(), checker((40, 180, 60), (20, 110, 40)));
+    textures.insert("bosque".to_string(), checker((90, 60, 30), (50, 30, 10)));
+    textures.insert("castillo".to_string(), checker((170, 170, 200), (90, 90, 120)));
+    textures.insert("burro".to_string(), disc(240, 200, 50));
+    textures.insert("fiona".to_string(), disc(200, 80, 120));
+    textures.insert("pasto".to_string(), checker((60, 140, 40), (40, 100, 30)));
+    textures.insert("piedra".to_string(), checker((120, 120, 120), (80, 80, 80)));
+    textures.insert("cielo".to_string(), gradient());
+    textures.insert("puerta".to_string(), checker((150, 95, 50), (110, 60, 30)));
+    textures.insert("agua".to_string(), atlas(&[checker((30, 90, 200), (20, 60, 150)), checker((200, 220, 255), (30, 90, 200))]));
+    textures.insert("vidrio".to_string(), TextureData::solid(150, 200, 230));
+    textures.insert("reja".to_string(), bars());
+    textures.insert("moneda".to_string(), disc(250, 210, 40));
+    textures.insert("cebolla".to_string(), disc(200, 150, 220));
+    // 8 vistas de un personaje, cada una de otro color
+    let views: Vec<TextureData> = (0..8).map(|i| disc(255 - i * 30, 40 + i * 25, 60 + i * 20)).collect();
+    textures.insert("gato".to_string(), atlas(&views));
+    textures
+}
+
+struct Fixture {
+    def: LevelDef,
+    assets: SceneAssets,
+}
+
+fn fixture() -> Fixture {
+    fixture_from(&common::source())
+}
+
+fn fixture_from(src: &str) -> Fixture {
+    let def = LevelDef::parse(src).expect("mapa de prueba válido");
+    let assets = SceneAssets::from_level(&def, &test_textures());
+    Fixture { def, assets }
+}
+
+fn render(fx: &Fixture, state: &LevelState, pos: Vec2, angle: f32) -> Framebuffer {
+    render_at(fx, state, pos, angle, 0.5)
+}
+
+/// Como `render`, pero en el instante `t` (segundos) para lo que depende del tiempo.
+fn render_at(fx: &Fixture, state: &LevelState, pos: Vec2, angle: f32, t: f32) -> Framebuffer {
+    let mut cam = Camera::new(pos, vec2(1.0, 0.0));
+    cam.rotate(angle);
+    let mut fb = Framebuffer::new(WIDTH, HEIGHT);
+    render_frame(&mut fb, &cam, state, &fx.assets, &[], t);
+    fb
+}
+
+fn check_golden(name: &str, fb: &Framebuffer) {
+    let path = format!("tests/golden/{}.png", name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fb.save_png(&path);
+        return;
+    }
+
+    let reference = TextureData::load(&path)
+        .unwrap_or_else(|e| panic!("falta la referencia {} ({}); genérala con UPDATE_GOLDEN=1", path, e));
+    assert_eq!((reference.width, reference.height), (fb.width, fb.height), "{}: tamaño distinto", name);
+
+    let channel = |p: u32, shift: u32| (p >> shift) & 0xff;
+    let differing = fb
+        .pixels
+        .iter()
+        .zip(&reference.pixels)
+        .filter(|&(&a, &b)| [16, 8, 0].iter().any(|&s| channel(a, s).abs_diff(channel(b, s)) > CHANNEL_TOLERANCE))
+        .count();
+    let ratio = differing as f32 / fb.pixels.len() as f32;
+    if ratio > MAX_DIFF_RATIO {
+        std::fs::create_dir_all("target/golden-actual").unwrap();
+        let actual = format!("target/golden-actual/{}.png", name);
+        fb.save_png(&actual);
+        panic!("{}: {} píxeles distintos ({:.2}%), ver {}", name, differing, ratio * 100.0, actual);
+    }
+}
+
+#[test]
+fn spawn_looking_east() {
+    let fx = fixture();
+    let state = LevelState::new(&fx.def);
+    check_golden("spawn_este", &render(&fx, &state, fx.def.spawn, 0.0));
+}
+
+#[test]
+fn diagonal_view_shows_wall_edges() {
+    let fx = fixture();
+    let state = LevelState::new(&fx.def);
+    check_golden("diagonal", &render(&fx, &state, vec2(2.5, 6.3), -0.8));
+}
+
+#[test]
+fn friend_sprite_in_front() {
+    let fx = fixture();
+    let state = LevelState::new(&fx.def);
+    // mirando al norte desde debajo del amigo (celda 4,4)
+    check_golden("amigo", &render(&fx, &state, vec2(4.5, 6.5), -std::f32::consts::FRAC_PI_2));
+}
+
+#[test]
+fn closed_exit_is_a_wall_and_open_exit_is_a_sprite() {
+    let fx = fixture();
+    let pos = vec2(6.5, 4.5);
+    let angle = -std::f32::consts::FRAC_PI_2;
+
+    let closed = LevelState::new(&fx.def);
+    check_golden("salida_cerrada", &render(&fx, &closed, pos, angle));
+
+    let mut open = LevelState::new(&fx.def);
+    open.coins = 0;
+    check_golden("salida_abierta", &render(&fx, &open, pos, angle));
+}
+
+#[test]
+fn wall_up_close() {
+    let fx = fixture();
+    let state = LevelState::new(&fx.def);
+    check_golden("pared_cerca", &render(&fx, &state, vec2(1.5, 6.15), -std::f32::consts::FRAC_PI_2));
+}
+
+#[test]
+fn textured_floor_ceiling_and_sky() {
+    let base = common::source();
+    // pasto en la mitad izquierda, piedra en la derecha; techo de piedra solo sobre la fila 6
+    let src = format!(
+        "textura pasto = pasto\ntextura piedra = piedra\ntextura cielo = cielo\n\
+         suelo 1 = pasto\nsuelo 2 = piedra\ntecho 1 = piedra\ncielo = cielo\n{}\n\
+         [suelo]\n11112222\n11112222\n11112222\n11112222\n11112222\n11112222\n11112222\n11112222\n\
+         [techo]\n00000000\n00000000\n00000000\n00000000\n00000000\n00000000\n00111100\n00000000\n",
+        base
+    );
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("suelo_techo_cielo", &render(&fx, &state, fx.def.spawn, 0.0));
+    check_golden("suelo_techo_cielo_girado", &render(&fx, &state, vec2(6.5, 6.5), -2.4));
+}
+
+#[test]
+fn fog_covers_distant_walls_and_sprites() {
+    let base = common::source();
+    let fx = fixture_from(&format!("niebla = color=40,50,40 inicio=1 fin=5\n{}", base));
+    let state = LevelState::new(&fx.def);
+    check_golden("niebla", &render(&fx, &state, fx.def.spawn, 0.0));
+    check_golden("niebla_amigo", &render(&fx, &state, vec2(4.5, 6.5), -std::f32::consts::FRAC_PI_2));
+}
+
+#[test]
+fn point_lights_in_a_dark_level() {
+    let base = common::source();
+    let fx = fixture_from(&format!(
+        "luz_ambiente = 0.2\n\
+         luz = pos=2.5,6.5 color=255,160,60 radio=4 parpadeo=0.3\n\
+         luz = celda=2 color=120,160,255 radio=2.5 intensidad=1.5\n{}",
+        base
+    ));
+    let state = LevelState::new(&fx.def);
+    check_golden("luces", &render(&fx, &state, fx.def.spawn, 0.0));
+    check_golden("luces_amigo", &render(&fx, &state, vec2(4.5, 6.5), -std::f32::consts::FRAC_PI_2));
+}
+
+#[test]
+fn sliding_door_recessed_in_its_cell() {
+    // puerta (código 5) en el pasillo de abajo, dos celdas delante del spawn
+    let base = common::source();
+    let src = format!("textura puerta = puerta\npuerta 5 = textura=puerta\n{}", base).replace("10000001\n11111111", "10050001\n11111111");
+    let fx = fixture_from(&src);
+
+    let mut state = LevelState::new(&fx.def);
+    check_golden("puerta_cerrada", &render(&fx, &state, fx.def.spawn, 0.0));
+
+    state.doors[0].open = 0.5;
+    check_golden("puerta_entreabierta", &render(&fx, &state, fx.def.spawn, 0.2));
+}
+
+#[test]
+fn low_and_tall_walls() {
+    // muros interiores bajos (se ve por encima), borde derecho y superior más altos
+    let base = common::source();
+    let src = format!(
+        "altura 1 = 0.4\naltura 2 = 2\naltura 3 = 2.5\n{}\n\
+         [alturas]\n33333333\n00000002\n01101002\n00101002\n00000102\n00101002\n00000002\n00000002\n",
+        base
+    );
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("alturas", &render(&fx, &state, fx.def.spawn, -0.6));
+    check_golden("alturas_torre", &render(&fx, &state, vec2(6.5, 6.5), -2.2));
+}
+
+#[test]
+fn per_face_wall_textures() {
+    // las paredes de bosque muestran otra textura en su cara oeste y norte
+    let base = common::source();
+    let src = base.replace("pared 2 = bosque", "pared 2 = textura=bosque oeste=planicie norte=castillo");
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("caras", &render(&fx, &state, vec2(3.5, 6.5), -0.5));
+}
+
+#[test]
+fn animated_wall_changes_frame_with_time() {
+    // la pared del fondo del pasillo es un atlas de dos cuadros a 2 fps
+    let base = common::source();
+    let src = format!("textura agua = agua\nanimacion agua = cuadros=2 fps=2\n{}", base)
+        .replace("pared 3 = castillo", "pared 3 = castillo\npared 4 = agua")
+        .replace("10000002\n11112222", "10000004\n11112222");
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("agua_cuadro0", &render_at(&fx, &state, fx.def.spawn, 0.0, 0.1));
+    check_golden("agua_cuadro1", &render_at(&fx, &state, fx.def.spawn, 0.0, 0.6));
+}
+
+#[test]
+fn directional_sprite_picks_view_from_camera_angle() {
+    // el amigo mira al sur: desde el sur se ve de frente (vista 0), desde el oeste su lado derecho (vista 2)
+    let base = common::source();
+    let src = format!("textura gato = gato\n{}", base).replace("amigo 2 = sprite=burro", "amigo 2 = sprite=gato angulos=8 mira=0,1");
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("sprite_frente", &render(&fx, &state, vec2(4.5, 6.5), -std::f32::consts::FRAC_PI_2));
+    check_golden("sprite_costado", &render(&fx, &state, vec2(1.5, 4.5), 0.0));
+}
+
+#[test]
+fn translucent_walls_and_sprites() {
+    // una pared de vidrio (o una reja) entre el pasillo de abajo y el amigo
+    let base = common::source();
+    let with_window = |faces: &str| {
+        format!("textura vidrio = vidrio\ntextura reja = reja\n{}", base)
+            .replace("pared 3 = castillo", &format!("pared 3 = castillo\npared 4 = {}", faces))
+            .replace("11010001\n10000001", "11011001\n10000001")
+            .replace("11020002\n10000002", "11024002\n10000002")
+    };
+    let view = (vec2(4.5, 6.5), -std::f32::consts::FRAC_PI_2);
+
+    let fx = fixture_from(&with_window("textura=vidrio opacidad=0.4"));
+    let state = LevelState::new(&fx.def);
+    check_golden("vidrio", &render(&fx, &state, view.0, view.1));
+
+    let fx = fixture_from(&with_window("reja"));
+    let state = LevelState::new(&fx.def);
+    check_golden("reja", &render(&fx, &state, view.0, view.1));
+
+    let fx = fixture_from(&base.replace("amigo 2 = sprite=burro", "amigo 2 = sprite=burro opacidad=0.5"));
+    let state = LevelState::new(&fx.def);
+    check_golden("amigo_fantasma", &render(&fx, &state, view.0, view.1));
+}
+
+#[test]
+fn items_placed_from_the_objects_layer() {
+    // monedas a lo largo del pasillo de abajo y una cebolla al final
+    let base = common::source();
+    let src = format!(
+        "textura moneda = moneda\ntextura cebolla = cebolla\n\
+         objeto 1 = tipo=moneda sprite=moneda\nobjeto 2 = tipo=cebolla sprite=cebolla escala=0.4\n{}\n\
+         [objetos]\n00000000\n00000000\n00000000\n00000000\n00000000\n00000000\n00111020\n00000000\n",
+        base
+    );
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("objetos", &render(&fx, &state, fx.def.spawn, 0.0));
+}
+
+#[test]
+fn enemy_stands_on_the_floor_facing_its_route() {
+    // un guardia en el pasillo de abajo que empieza mirando al este: desde el inicio se le ve la espalda (vista 4)
+    let base = common::source();
+    let src = format!("textura gato = gato\nenemigo = sprite=gato angulos=8 ruta=4,6;6,6\n{}", base);
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("enemigo", &render(&fx, &state, fx.def.spawn, 0.0));
+}
+
+#[test]
+fn ghost_is_drawn_translucent_in_front_of_the_player() {
+    // el fantasma de la mejor partida, medio transparente, un poco más adelante en el pasillo
+    let base = common::source();
+    let src = format!("textura gato = gato\nfantasma = sprite=gato angulos=8\n{}", base);
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    let sprite = fx.assets.ghost.as_ref().expect("sprite del fantasma");
+    assert_eq!(sprite.opacity, GHOST_OPACITY);
+    let ghost = [Billboard { pos: fx.def.spawn + vec2(1.5, 0.0), facing: vec2(1.0, 0.0), walking: false, sprite }];
+    let mut fb = Framebuffer::new(WIDTH, HEIGHT);
+    render_frame(&mut fb, &Camera::new(fx.def.spawn, vec2(1.0, 0.0)), &state, &fx.assets, &ghost, 0.5);
+    check_golden("fantasma", &fb);
+}
+
+#[test]
+fn low_wall_hides_the_bottom_of_a_sprite_behind_it() {
+    // un muro bajo en el pasillo, entre el jugador y el amigo: el amigo asoma solo por encima
+    let base = common::source();
+    let src = format!(
+        "altura 1 = 0.4\n{}\n\
+         [alturas]\n00000000\n00000000\n00000000\n00000000\n00010000\n00000000\n00000000\n00000000\n",
+        base
+    )
+    .replace("amigo 2 = sprite=burro", "amigo 2 = sprite=burro escala=1")
+    .replace("10002101\n11010001", "10012101\n11010001")
+    .replace("10000202\n11020002", "10020202\n11020002");
+    let fx = fixture_from(&src);
+    let state = LevelState::new(&fx.def);
+    check_golden("seto_amigo", &render(&fx, &state, vec2(1.5, 4.5), 0.0));
+}
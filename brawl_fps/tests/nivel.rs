@@ -0,0 +1,109 @@
+// Pruebas del parser de niveles: los archivos mal formados se rechazan con un `ParseError` que
+// señala la línea y la columna del problema.
+
+use brawl_fps::nivel::{LevelDef, ParseError};
+
+mod common;
+use common::source;
+
+/// Línea, columna y mensaje del error al parsear `src`.
+fn error(src: &str) -> (usize, usize, String) {
+    let ParseError { line, column, message } = LevelDef::parse(src).expect_err("el nivel debería ser inválido");
+    (line, column, message)
+}
+
+#[test]
+fn ragged_map_rows_point_at_the_first_missing_or_extra_cell() {
+    // la tercera fila del mapa está en la línea 23
+    let (line, column, message) = error(&source().replace("10110101", "1011010"));
+    assert_eq!((line, column), (23, 8));
+    assert_eq!(message, "la fila tiene 7 celdas pero se esperaban 8");
+    let (line, column, _) = error(&source().replace("10110101", "101101011"));
+    assert_eq!((line, column), (23, 9));
+    let (line, column, _) = error(&source().replace("10110101", "10x10101"));
+    assert_eq!((line, column), (23, 3));
+}
+
+#[test]
+fn unknown_keys_and_fields_are_reported_where_they_start() {
+    let (line, column, message) = error(&format!("velocidad = 3\n{}", source()));
+    assert_eq!((line, column), (1, 1));
+    assert_eq!(message, "clave desconocida `velocidad`");
+    // con sangría, la columna es la de la clave
+    let (line, column, _) = error(&format!("\n   gravedad = 9.8\n{}", source()));
+    assert_eq!((line, column), (2, 4));
+
+    let (line, column, message) = error(&source().replace("amigo 2 = sprite=burro", "amigo 2 = sprite=burro color=rojo"));
+    assert_eq!((line, column), (13, 24));
+    assert_eq!(message, "campo desconocido `color`");
+}
+
+#[test]
+fn missing_map_section_is_reported_after_the_last_line() {
+    let src = source();
+    let without_map = &src[..src.find("[mapa]").unwrap()];
+    let (line, column, message) = error(without_map);
+    assert_eq!((line, column), (without_map.lines().count() + 1, 1));
+    assert_eq!(message, "falta la sección `[mapa]`");
+}
+
+#[test]
+fn player_radius_must_leave_room_in_a_corridor() {
+    let with_radius = |r: &str| format!("radio_jugador = {}\n{}", r, source());
+    assert_eq!(LevelDef::parse(&with_radius("0.3")).unwrap().player_radius, 0.3);
+    for bad in ["0", "-0.1", "0.5", "2"] {
+        let (line, column, message) = error(&with_radius(bad));
+        assert_eq!((line, column), (1, 17), "radio_jugador = {}", bad);
+        assert_eq!(message, "el radio del jugador tiene que estar entre 0 y 0.5");
+    }
+}
+
+#[test]
+fn enemy_damage_must_be_a_positive_whole_number() {
+    let ok = format!("enemigo = sprite=burro ruta=1,6;3,6 dano=30\n{}", source());
+    assert_eq!(LevelDef::parse(&ok).unwrap().enemies[0].damage, 30);
+    for bad in ["2.5", "0.5", "0", "-3"] {
+        let (line, column, message) = error(&ok.replace("dano=30", &format!("dano={}", bad)));
+        assert_eq!((line, column), (1, 42), "dano={}", bad);
+        assert!(message.contains(bad), "{}", message);
+    }
+}
+
+#[test]
+fn nan_and_infinite_numbers_are_rejected_where_they_appear() {
+    for (extra, column) in [
+        ("radio_jugador = NaN", 17),
+        ("altura 1 = inf", 12),
+        ("objeto 1 = tipo=velocidad sprite=burro factor=-inf", 47),
+        ("objeto 1 = tipo=velocidad sprite=burro duracion=inf", 49),
+        ("enemigo = sprite=burro ruta=1,6;3,6 escala=NaN", 44),
+    ] {
+        let (line, col, message) = error(&format!("{}\n{}", extra, source()));
+        assert_eq!((line, col), (1, column), "{}", extra);
+        assert!(message.contains("NaN") || message.contains("inf"), "{}", message);
+    }
+    let (line, column, _) = error(&source().replace("spawn = 1.5 6.5", "spawn = NaN 6.5"));
+    assert_eq!((line, column), (4, 9));
+}
+
+#[test]
+fn spawn_errors_point_at_the_spawn_line() {
+    // `spawn = 1.5 6.5` es la línea 4; el valor empieza en la columna 9
+    let (line, column, message) = error(&source().replace("spawn = 1.5 6.5", "spawn = 0.5 6.5"));
+    assert_eq!((line, column), (4, 9));
+    assert_eq!(message, "el spawn cae sobre una celda no vacía");
+    let (line, column, message) = error(&source().replace("spawn = 1.5 6.5", "spawn = 20 6.5"));
+    assert_eq!((line, column), (4, 9));
+    assert_eq!(message, "el spawn está fuera del mapa");
+}
+
+#[test]
+fn undeclared_map_codes_are_rejected_at_their_cell() {
+    // la quinta fila del mapa (línea 25) pasa a tener un 7 en la columna 6
+    let (line, column, message) = error(&source().replace("10002101", "10002701"));
+    assert_eq!((line, column), (25, 6));
+    assert_eq!(message, "código 7 no declarado con `amigo 7 = ...` ni `puerta 7 = ...`");
+    // sin `amigo 2` el 2 del mapa tampoco vale
+    let (line, column, _) = error(&source().replace("amigo 2 = sprite=burro\n", ""));
+    assert_eq!((line, column), (24, 5));
+}
@@ -0,0 +1,145 @@
+// Pruebas de los niveles que trae el juego: un piloto automático juega cada uno sin ventana
+// (con `Game`) hasta ganarlo, así un cambio en un mapa que lo deje sin solución, o una regresión
+// en la simulación, se detecta sin tener que jugarlo a mano.
+
+use std::collections::{HashMap, VecDeque};
+
+use brawl_fps::nivel::{LevelDef, PickupKind, CELL_EXIT, DOOR_PASSABLE};
+use brawl_fps::simulacion::{Game, GameEvent, InputFrame, TICK_RATE};
+use macroquad::math::{vec2, Vec2};
+
+/// Segundos de juego que tiene el piloto para ganar cada nivel.
+const MAX_SECS: u32 = 300;
+
+type Cell = (i32, i32);
+
+fn cell_of(p: Vec2) -> Cell {
+    (p.x.floor() as i32, p.y.floor() as i32)
+}
+
+fn center((x, y): Cell) -> Vec2 {
+    vec2(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+/// Si el piloto puede pasar por `c`: celdas libres y puertas que se pueden abrir, sin pisar peligros.
+fn walkable(game: &Game, c: Cell) -> bool {
+    if game.def.hazard_at(center(c)).is_some() {
+        return false;
+    }
+    match game.level.door_at(c.0, c.1) {
+        Some(door) => game.level.door_lock(game.def, door).is_none(),
+        None => !game.level.blocks_at(c.0, c.1),
+    }
+}
+
+/// Camino más corto (BFS) desde `from` hasta la más cercana de `targets`: las celdas a recorrer
+/// sin incluir `from`, la próxima primero; `None` si no se llega a ninguna.
+fn path_to(game: &Game, from: Cell, targets: &[Cell]) -> Option<Vec<Cell>> {
+    let mut came_from: HashMap<Cell, Cell> = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(cell) = queue.pop_front() {
+        if targets.contains(&cell) {
+            let mut path = vec![cell];
+            while let Some(&prev) = came_from.get(path.last().unwrap()).filter(|&&p| p != from) {
+                path.push(prev);
+            }
+            if cell == from {
+                path.clear();
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = (cell.0 + dx, cell.1 + dy);
+            if !came_from.contains_key(&next) && walkable(game, next) {
+                came_from.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Adónde ir: los amigos que faltan y, con todos encontrados, la salida.
+fn targets(game: &Game) -> Vec<Cell> {
+    let friends: Vec<Cell> =
+        game.level.pickups.iter().filter(|p| matches!(p.kind, PickupKind::Friend(_))).map(|p| cell_of(p.pos)).collect();
+    if !friends.is_empty() {
+        return friends;
+    }
+    let map = &game.level.map;
+    (0..map.height as i32).flat_map(|y| (0..map.width as i32).map(move |x| (x, y))).filter(|&(x, y)| map.get(x, y) == Some(CELL_EXIT)).collect()
+}
+
+/// Entrada de un paso: va hacia el centro de la próxima celda del camino (avanzando y de costado
+/// a la vez, como un jugador) mientras gira para mirarla; ante una puerta cerrada se arrima, la
+/// abre y espera a que deje pasar.
+fn autopilot(game: &Game) -> InputFrame {
+    let here = cell_of(game.cam.pos);
+    let path = path_to(game, here, &targets(game)).unwrap_or_else(|| panic!("{}: sin camino desde {:?}", game.def.name, here));
+    let next = path.first().copied().unwrap_or(here);
+    let to = center(next) - game.cam.pos;
+    let dir = game.cam.dir;
+    let angle = dir.perp_dot(to).atan2(dir.dot(to));
+    let turn = angle.clamp(-0.1, 0.1);
+    if let Some(door) = game.level.door_at(next.0, next.1)
+        && door.open < DOOR_PASSABLE
+    {
+        let facing = angle.abs() < 0.2;
+        return InputFrame { forward: facing, turn, use_door: facing && !door.opening, ..Default::default() };
+    }
+    let (ahead, side) = (dir.dot(to.normalize_or_zero()), vec2(-dir.y, dir.x).dot(to.normalize_or_zero()));
+    InputFrame { forward: ahead > 0.4, back: ahead < -0.4, right: side > 0.4, left: side < -0.4, turn, ..Default::default() }
+}
+
+fn load(path: &str) -> LevelDef {
+    LevelDef::load(path).expect("nivel válido")
+}
+
+/// Juega el nivel con el piloto automático hasta que termina o se acaba el tiempo. Devuelve cómo
+/// quedó la partida y todo lo que pasó.
+fn solve(def: &LevelDef) -> (Game<'_>, Vec<GameEvent>) {
+    let mut game = Game::new(def);
+    let mut events = Vec::new();
+    for _ in 0..MAX_SECS * TICK_RATE {
+        if game.finished() {
+            break;
+        }
+        let input = autopilot(&game);
+        events.extend(game.step(&input));
+    }
+    assert!(game.exit_unlocked(), "{}: faltan {} amigos", def.name, game.level.coins);
+    assert!(game.level.won, "{}: no llegó a la salida (vida {}, en {:?})", def.name, game.level.health, game.cam.pos);
+    assert_eq!(events.last(), Some(&GameEvent::Won));
+    (game, events)
+}
+
+fn friends_found(events: &[GameEvent]) -> Vec<i32> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            GameEvent::Picked(PickupKind::Friend(cell)) => Some(*cell),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn nivel1_can_be_won() {
+    let def = load("niveles/nivel1.txt");
+    let (game, events) = solve(&def);
+    assert_eq!(friends_found(&events).len(), def.collectibles.len());
+    assert!(game.level.elapsed < def.par_time.unwrap(), "tardó {:.1} s", game.level.elapsed);
+}
+
+#[test]
+fn nivel2_can_be_won_through_the_locked_door() {
+    let def = load("niveles/nivel2.txt");
+    let (game, events) = solve(&def);
+    assert_eq!(friends_found(&events).len(), def.collectibles.len());
+    // la llave dorada la da el gato y sin ella la puerta del castillo no abre
+    assert!(game.level.keys.contains("dorada"));
+    assert!(game.level.doors.iter().all(|d| d.opening));
+    assert!(!events.iter().any(|e| matches!(e, GameEvent::HazardHit(_))), "el piloto esquiva el pantano");
+    assert!(game.level.elapsed < def.par_time.unwrap(), "tardó {:.1} s", game.level.elapsed);
+}
@@ -0,0 +1,103 @@
+// Pruebas de los objetos y amigos que se recogen: `LevelState::collect_near` y los efectos que
+// dejan (puntos, llaves, vida, velocidad), sobre el mapa de prueba con un `[objetos]` agregado.
+
+use brawl_fps::nivel::{LevelDef, LevelState, PickupKind, MAX_HEALTH, PICKUP_RADIUS};
+use macroquad::math::vec2;
+
+mod common;
+use common::source;
+
+/// El nivel `base` (el mapa de prueba, quizás retocado) con una moneda, una llave, una cebolla y
+/// una poción en el pasillo de abajo, de (2, 6) a (5, 6).
+fn with_items(base: &str) -> LevelDef {
+    let items = "\
+objeto 1 = tipo=moneda sprite=burro valor=10
+objeto 2 = tipo=llave sprite=burro llave=roja
+objeto 3 = tipo=cebolla sprite=burro vida=25
+objeto 4 = tipo=velocidad sprite=burro factor=2 duracion=1
+";
+    let grid = "[objetos]\n00000000\n00000000\n00000000\n00000000\n00000000\n00000000\n00123400\n00000000\n";
+    LevelDef::parse(&format!("{}\n{}\n{}", items, base, grid)).unwrap()
+}
+
+#[test]
+fn items_are_picked_up_only_within_the_pickup_radius() {
+    let def = with_items(&source());
+    let mut state = LevelState::new(&def);
+    // justo en el borde entre la moneda y la llave no alcanza ninguna
+    assert!(state.collect_near(&def, vec2(2.5 + PICKUP_RADIUS, 6.5)).is_empty());
+    assert_eq!(state.collect_near(&def, vec2(2.5 + PICKUP_RADIUS - 0.01, 6.5)), [PickupKind::Item(1)]);
+    assert_eq!((state.score, state.items_collected), (10, 1));
+    // ya no está
+    assert!(state.collect_near(&def, vec2(2.5, 6.5)).is_empty());
+}
+
+#[test]
+fn keys_come_from_items_and_from_friends() {
+    let def = with_items(&source().replace("amigo 2 = sprite=burro", "amigo 2 = sprite=burro llave=verde"));
+    let mut state = LevelState::new(&def);
+    assert_eq!(state.collect_near(&def, vec2(3.5, 6.5)), [PickupKind::Item(2)]);
+    assert!(state.keys.contains("roja"));
+
+    assert_eq!(state.collect_near(&def, vec2(4.5, 4.5)), [PickupKind::Friend(2)]);
+    assert!(state.keys.contains("verde"));
+    assert_eq!((state.coins, state.friends_found), (0, 1));
+    assert_eq!(state.map.get(4, 4), Some(0), "el amigo deja libre su celda");
+}
+
+#[test]
+fn onions_stay_on_the_floor_at_full_health() {
+    let def = with_items(&source());
+    let mut state = LevelState::new(&def);
+    assert!(state.collect_near(&def, vec2(4.5, 6.5)).is_empty());
+    assert_eq!(state.pickups.len(), 5);
+
+    state.health = MAX_HEALTH - 10;
+    assert_eq!(state.collect_near(&def, vec2(4.5, 6.5)), [PickupKind::Item(3)]);
+    assert_eq!(state.health, MAX_HEALTH, "cura 25 pero no pasa del máximo");
+}
+
+#[test]
+fn speed_boost_wears_off() {
+    let def = with_items(&source());
+    let mut state = LevelState::new(&def);
+    assert_eq!(state.speed_factor(), 1.0);
+    assert_eq!(state.collect_near(&def, vec2(5.5, 6.5)), [PickupKind::Item(4)]);
+    assert_eq!(state.speed_factor(), 2.0);
+    state.update_effects(0.5);
+    assert_eq!(state.speed_factor(), 2.0);
+    state.update_effects(0.5);
+    assert_eq!(state.speed_factor(), 1.0);
+    assert!(state.speed_boost.is_none());
+}
+
+#[test]
+fn required_friends_caps_how_many_must_be_found() {
+    // un segundo amigo en (5, 6)
+    let base = source().replacen("10000001", "10000201", 1);
+    let def = LevelDef::parse(&format!("amigos_requeridos = 1\n{}", base)).unwrap();
+    let mut state = LevelState::new(&def);
+    assert_eq!(state.coins, 1);
+
+    state.collect_near(&def, vec2(5.5, 6.5));
+    assert_eq!(state.coins, 0, "con uno alcanza para abrir la salida");
+    state.collect_near(&def, vec2(4.5, 4.5));
+    assert_eq!((state.coins, state.friends_found), (0, 2), "no baja de 0");
+    assert!(state.pickups.is_empty());
+
+    let all = LevelDef::parse(&base).unwrap();
+    assert_eq!(LevelState::new(&all).coins, 2);
+}
+
+#[test]
+fn item_values_must_be_positive_whole_numbers() {
+    let src = format!("objeto 1 = tipo=moneda sprite=burro valor=10\nobjeto 3 = tipo=cebolla sprite=burro vida=25\n{}", source());
+    assert!(LevelDef::parse(&src).is_ok());
+    // `valor` y `vida` arrancan en la columna 43 de su línea
+    for (from, to, line) in [("valor=10", "0.5", 1), ("valor=10", "0", 1), ("vida=25", "2.5", 2), ("vida=25", "0", 2)] {
+        let key = &from[..from.find('=').unwrap()];
+        let err = LevelDef::parse(&src.replace(from, &format!("{}={}", key, to))).expect_err(from);
+        assert_eq!((err.line, err.column), (line, 43), "{}={}", key, to);
+        assert!(err.message.contains(to), "{}", err.message);
+    }
+}
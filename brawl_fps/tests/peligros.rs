@@ -0,0 +1,54 @@
+// Pruebas de los peligros y del daño al jugador: `LevelState::hurt`, `touch_hazard` y la
+// invulnerabilidad que sigue a cada golpe, sobre el mapa de prueba con un `[peligros]` agregado.
+
+use brawl_fps::nivel::{LevelState, INVULNERABLE_SECS, MAX_HEALTH};
+use macroquad::math::vec2;
+
+mod common;
+use common::hazard_level;
+
+#[test]
+fn hurt_respects_invulnerability_and_clamps_health() {
+    let def = hazard_level(10);
+    let mut state = LevelState::new(&def);
+    assert!(state.hurt(30));
+    assert_eq!((state.health, state.invulnerable), (70, INVULNERABLE_SECS));
+    // otro golpe (del enemigo o del peligro) no cuenta mientras dura la invulnerabilidad
+    assert!(!state.hurt(30));
+    assert!(state.touch_hazard(&def, vec2(3.5, 6.5)).is_none());
+    assert_eq!(state.health, 70);
+
+    state.update_effects(INVULNERABLE_SECS);
+    assert!(state.touch_hazard(&def, vec2(1.5, 6.5)).is_none(), "fuera del peligro");
+    assert_eq!(state.touch_hazard(&def, vec2(3.5, 6.5)).map(|h| h.damage), Some(10));
+    assert_eq!(state.health, 60);
+
+    state.update_effects(INVULNERABLE_SECS);
+    assert!(state.hurt(MAX_HEALTH));
+    assert_eq!((state.health, state.damage_taken), (0, MAX_HEALTH));
+    assert!(state.lost);
+    state.update_effects(INVULNERABLE_SECS);
+    assert!(!state.hurt(10), "ya perdió");
+}
+
+#[test]
+fn standing_on_a_hazard_hurts_once_per_invulnerable_spell_until_lost() {
+    let def = hazard_level(40);
+    let mut state = LevelState::new(&def);
+    // como el bucle del juego: efectos y después el peligro, a 60 pasos por segundo
+    let dt = 1.0 / 60.0;
+    let mut hits = Vec::new();
+    for tick in 0..200 {
+        state.update_effects(dt);
+        if state.touch_hazard(&def, vec2(3.5, 6.5)).is_some() {
+            hits.push((tick, state.health, state.lost));
+        }
+    }
+    let healths: Vec<_> = hits.iter().map(|&(_, health, lost)| (health, lost)).collect();
+    assert_eq!(healths, [(60, false), (20, false), (0, true)]);
+    // un paso de tolerancia: restar `dt` en f32 puede dejar un resto mínimo de invulnerabilidad
+    let spell = (INVULNERABLE_SECS / dt).round() as u32;
+    for gap in [hits[1].0 - hits[0].0, hits[2].0 - hits[1].0] {
+        assert!((spell..=spell + 1).contains(&gap), "{} pasos entre golpes", gap);
+    }
+}
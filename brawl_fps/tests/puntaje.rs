@@ -0,0 +1,92 @@
+// Pruebas del puntaje de fin de partida: `RunResults::new` solo mira la definición del nivel y
+// las estadísticas de `LevelState`, así que se arman a mano.
+
+use brawl_fps::nivel::{LevelDef, LevelState, MAX_PAR_SECS};
+use brawl_fps::puntaje::{RunResults, ScoreLine, DAMAGE_PENALTY, FRIEND_POINTS, PAR_POINTS_PER_SEC};
+
+mod common;
+use common::level;
+
+fn points(results: &RunResults) -> Vec<i32> {
+    results.lines.iter().map(|l| l.points).collect()
+}
+
+#[test]
+fn levels_without_par_items_or_damage_only_count_friends() {
+    let def = level("", &[]);
+    let mut state = LevelState::new(&def);
+    state.friends_found = 1;
+    let results = RunResults::new(&def, &state);
+    assert_eq!(results.lines, [ScoreLine { label: "Amigos encontrados: 1".into(), points: FRIEND_POINTS }]);
+    assert_eq!(results.total, FRIEND_POINTS as u32);
+    assert_eq!(results.par_time, None);
+}
+
+#[test]
+fn par_bonus_rounds_to_the_nearest_point() {
+    let def = level("tiempo_par = 60", &[]);
+    let mut state = LevelState::new(&def);
+    // 2.25 s por debajo del par: 22.5 puntos, redondeado hacia arriba
+    state.elapsed = 57.75;
+    let results = RunResults::new(&def, &state);
+    assert_eq!(results.lines[1].label, "Tiempo: 0:57 (par 1:00)");
+    assert_eq!(points(&results), [0, 23]);
+
+    state.elapsed = 59.96;
+    assert_eq!(points(&RunResults::new(&def, &state)), [0, 0]);
+    // pasarse del par no resta
+    state.elapsed = 75.0;
+    assert_eq!(points(&RunResults::new(&def, &state)), [0, 0]);
+}
+
+#[test]
+fn damage_is_penalized_and_the_total_never_goes_below_zero() {
+    let def = level("enemigo = sprite=burro ruta=6,6;6,3\nobjeto 1 = tipo=moneda sprite=burro valor=10", &[]);
+    let mut state = LevelState::new(&def);
+    state.friends_found = 1;
+    state.items_collected = 2;
+    state.score = 20;
+    state.damage_taken = 30;
+    let results = RunResults::new(&def, &state);
+    assert_eq!(results.lines[1].label, "Objetos recogidos: 2");
+    assert_eq!(results.lines[2].label, "Daño recibido: 30");
+    assert_eq!(points(&results), [FRIEND_POINTS, 20, -30 * DAMAGE_PENALTY]);
+    assert_eq!(results.total, 60);
+
+    state.damage_taken = 90;
+    let results = RunResults::new(&def, &state);
+    assert_eq!(points(&results), [FRIEND_POINTS, 20, -90 * DAMAGE_PENALTY]);
+    assert_eq!(results.total, 0);
+}
+
+#[test]
+fn time_only_counts_simulated_steps() {
+    let def = level("tiempo_par = 60", &[]);
+    let mut state = LevelState::new(&def);
+    // el reloj avanza solo con `update_effects`, que en pausa no se llama
+    for _ in 0..120 {
+        state.update_effects(1.0 / 60.0);
+    }
+    let results = RunResults::new(&def, &state);
+    assert!((results.time - 2.0).abs() < 1e-3, "{}", results.time);
+    assert_eq!(points(&results), [0, 580]);
+}
+
+#[test]
+fn huge_par_times_are_rejected_and_big_totals_do_not_overflow() {
+    for bad in ["1e30", "inf", "NaN", "86401"] {
+        let err = LevelDef::parse(&format!("tiempo_par = {}\n{}", bad, common::source())).expect_err(bad);
+        assert_eq!((err.line, err.column), (1, 14), "tiempo_par = {}", bad);
+    }
+
+    // el par más largo que se acepta, con todos los puntos posibles de objetos
+    let def = level(&format!("tiempo_par = {}\nobjeto 1 = tipo=moneda sprite=burro valor=10", MAX_PAR_SECS), &[]);
+    let mut state = LevelState::new(&def);
+    state.friends_found = 1;
+    state.score = u32::MAX;
+    let results = RunResults::new(&def, &state);
+    assert_eq!(points(&results), [FRIEND_POINTS, i32::MAX, (MAX_PAR_SECS * PAR_POINTS_PER_SEC) as i32]);
+    // la suma pasa de i32::MAX sin desbordar
+    assert_eq!(results.total as i64, points(&results).iter().map(|&p| p as i64).sum::<i64>());
+    assert!(results.total > i32::MAX as u32);
+}
@@ -0,0 +1,49 @@
+// Pruebas del archivo de récords: ida y vuelta por texto, orden de la tabla y tolerancia a
+// archivos dañados o de otra versión.
+
+use brawl_fps::nivel::LevelState;
+use brawl_fps::puntaje::RunResults;
+use brawl_fps::records::{RecordTable, MAX_SCORES};
+
+mod common;
+use common::level;
+
+fn results(total: u32, time: f32) -> RunResults {
+    let def = level("", &[]);
+    let mut res = RunResults::new(&def, &LevelState::new(&def));
+    res.total = total;
+    res.time = time;
+    res
+}
+
+#[test]
+fn table_keeps_best_scores_and_time_and_survives_a_round_trip() {
+    let mut table = RecordTable::default();
+    for (i, (score, time)) in [(300, 50.0), (900, 80.0), (500, 40.0), (100, 90.0), (700, 60.0), (200, 70.0)].into_iter().enumerate() {
+        assert!(table.qualifies("Nivel 1", &results(score, time)));
+        table.submit("Nivel 1", &format!("p{}", i), &results(score, time));
+    }
+    let rec = table.level("Nivel 1").unwrap();
+    let scores: Vec<u32> = rec.scores.iter().map(|e| e.score).collect();
+    assert_eq!(scores.len(), MAX_SCORES);
+    assert_eq!(scores, [900, 700, 500, 300, 200]);
+    assert_eq!(rec.best_time.as_ref().map(|e| (e.initials.as_str(), e.time)), Some(("P2", 40.0)));
+    assert!(!table.qualifies("Nivel 1", &results(150, 45.0)));
+    assert!(table.qualifies("Nivel 1", &results(150, 39.0)));
+
+    assert_eq!(RecordTable::parse(&table.to_text()), Ok(table));
+}
+
+#[test]
+fn damaged_or_foreign_files_are_rejected() {
+    assert!(RecordTable::parse("[Nivel 1]\npuntaje = ABC 10 1.0\n").is_err());
+    assert!(RecordTable::parse("version = 99\n").is_err());
+    assert!(RecordTable::parse("version = 1\n[Nivel 1]\npuntaje = ABC diez 1.0\n").is_err());
+    assert!(RecordTable::parse("version = 1\npuntaje = ABC 10 1.0\n").is_err());
+
+    // al cargar, un archivo dañado se reemplaza por la tabla vacía
+    let path = std::env::temp_dir().join(format!("brawl_fps_records_{}.txt", std::process::id()));
+    std::fs::write(&path, "\u{0}\u{1}basura").unwrap();
+    assert_eq!(RecordTable::load(&path), RecordTable::default());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,87 @@
+// Pruebas de las repeticiones: una partida grabada, pasada a texto y vuelta a leer, se
+// reproduce exacta (mismo recorrido, mismas recolecciones, mismo tiempo).
+
+use brawl_fps::nivel::LevelDef;
+use brawl_fps::repeticion::{replay, Ghost, Recording, MAX_REPLAY_TICKS};
+use brawl_fps::simulacion::{update, Game, InputFrame, TICK_DT};
+use macroquad::math::{vec2, Vec2};
+
+mod common;
+use common::level;
+
+/// Juega el mapa de prueba llevando al jugador por `waypoints` (gira hacia el próximo y avanza
+/// cuando lo tiene enfrente) y graba la entrada de cada paso.
+fn play_through<'a>(def: &'a LevelDef, waypoints: &[Vec2]) -> (Game<'a>, Recording) {
+    let mut game = Game::new(def);
+    let mut rec = Recording::new(&def.name);
+    let mut next = 0;
+    while next < waypoints.len() && rec.inputs.len() < 3000 && !game.finished() {
+        let to = waypoints[next] - game.cam.pos;
+        if to.length() < 0.1 {
+            next += 1;
+            continue;
+        }
+        let angle = game.cam.dir.perp_dot(to).atan2(game.cam.dir.dot(to));
+        let input = InputFrame { forward: angle.abs() < 0.2, turn: angle.clamp(-0.08, 0.08), use_door: rec.inputs.len() == 10, ..Default::default() };
+        update(&mut game, &input, TICK_DT);
+        rec.inputs.push(input);
+    }
+    (game, rec)
+}
+
+#[test]
+fn recorded_run_replays_exactly_after_a_round_trip_through_text() {
+    let def = level("", &[]);
+    // por el pasillo de abajo hasta el amigo, y de ahí subiendo por la derecha hasta la salida
+    let route = [vec2(4.5, 6.5), vec2(4.5, 4.5), vec2(4.5, 5.5), vec2(6.5, 5.5), vec2(6.5, 1.5)];
+    let (game, rec) = play_through(&def, &route);
+    assert!(game.level.won, "el recorrido de prueba no llegó a la salida: {:?}", game.cam.pos);
+
+    let text = rec.to_text();
+    let loaded = Recording::parse(&text).expect("repetición válida");
+    assert_eq!(loaded, rec);
+    // los pasos iguales seguidos se guardan en una sola línea
+    assert!(text.lines().count() < rec.inputs.len());
+
+    let again = replay(&def, &loaded);
+    assert!(again.level.won);
+    assert_eq!(again.cam, game.cam);
+    assert_eq!(again.level.elapsed, game.level.elapsed);
+    assert_eq!(again.level.pickups, game.level.pickups);
+    assert!((loaded.duration() - game.level.elapsed).abs() < 1e-3);
+}
+
+#[test]
+fn ghost_follows_the_recorded_run_and_waits_at_the_end() {
+    let def = level("", &[]);
+    let route = [vec2(4.5, 6.5), vec2(4.5, 4.5), vec2(4.5, 5.5), vec2(6.5, 5.5), vec2(6.5, 1.5)];
+    let (game, rec) = play_through(&def, &route);
+    let ghost = Ghost::new(&def, &rec);
+    assert!(ghost.won);
+    assert_eq!(ghost.duration(), rec.duration());
+
+    // empieza en el spawn, va a la par de la partida y al terminar se queda en la salida
+    assert_eq!(ghost.cam_at(0, 0.5).pos, def.spawn);
+    assert!((1..=rec.inputs.len()).any(|t| ghost.walking_at(t)));
+    let end = rec.inputs.len();
+    assert!(ghost.cam_at(end, 1.0).pos.distance(game.cam.pos) < 1e-4);
+    assert!(ghost.cam_at(end + 100, 0.3).pos.distance(game.cam.pos) < 1e-4);
+    assert!(!ghost.walking_at(end + 1));
+}
+
+#[test]
+fn malformed_recordings_are_rejected() {
+    let ok = "version = 1\nnivel = Prueba\npasos_por_segundo = 60\n[pasos]\n2 F---- 0\n";
+    assert_eq!(Recording::parse(ok).map(|r| r.inputs.len()), Ok(2));
+    assert!(Recording::parse(&ok.replace("version = 1", "version = 2")).is_err());
+    assert!(Recording::parse(&ok.replace("= 60", "= 30")).is_err());
+    assert!(Recording::parse(&ok.replace("F----", "X----")).is_err());
+    assert!(Recording::parse(&ok.replace("2 F", "0 F")).is_err());
+    assert!(Recording::parse(&ok.replace("[pasos]\n2 F---- 0\n", "")).is_err());
+
+    // cantidades enormes se rechazan con la línea, sin intentar reservarlas
+    let huge = ok.replace("2 F---- 0", "1 ----- 0\n99999999999 F---- 0");
+    assert_eq!(Recording::parse(&huge).map_err(|e| e.line), Err(6));
+    let over = format!("{}{} ----- 0\n", ok, MAX_REPLAY_TICKS - 1);
+    assert!(Recording::parse(&over).is_err());
+}
@@ -0,0 +1,117 @@
+// Pruebas de la simulación a paso fijo: `update` solo depende de la entrada de cada paso, así
+// que se puede correr sin ventana.
+
+use brawl_fps::nivel::{LevelDef, INVULNERABLE_SECS, PLAYER_RADIUS};
+use brawl_fps::raycaster::Camera;
+use brawl_fps::simulacion::{update, Game, GameEvent, InputFrame, MOVE_SPEED, TICK_DT};
+use macroquad::math::vec2;
+
+mod common;
+use common::{hazard_level, level, source};
+
+#[test]
+fn one_second_of_ticks_moves_the_player_move_speed_cells() {
+    let def = level("", &[]);
+    let mut game = Game::new(&def);
+    let forward = InputFrame { forward: true, ..Default::default() };
+    game.run_for(1.0, &forward);
+    assert!((game.cam.pos.x - (def.spawn.x + MOVE_SPEED)).abs() < 1e-3, "{:?}", game.cam.pos);
+    assert!((game.level.elapsed - 1.0).abs() < 1e-3);
+
+    // la pared del fondo del pasillo lo frena a un radio de distancia
+    game.run_for(3.0, &forward);
+    assert!((game.cam.pos.x - (7.0 - PLAYER_RADIUS)).abs() < 1e-4, "{:?}", game.cam.pos);
+}
+
+#[test]
+fn walking_diagonally_into_a_wall_slides_along_it() {
+    let def = level("", &[]);
+    let mut game = Game::new(&def);
+    // mirando al sudeste por el pasillo de abajo: la pared de abajo desvía el avance hacia el este
+    game.step(&InputFrame { turn: 0.6, ..Default::default() });
+    game.run_for(1.0, &InputFrame { forward: true, ..Default::default() });
+    assert!((game.cam.pos.y - (7.0 - PLAYER_RADIUS)).abs() < 1e-4, "{:?}", game.cam.pos);
+    assert!(game.cam.pos.x > def.spawn.x + 1.5, "se quedó pegado a la pared: {:?}", game.cam.pos);
+}
+
+#[test]
+fn exit_keeps_the_player_out_until_friends_are_found() {
+    let def = level("radio_jugador = 0.3", &[]);
+    let mut game = Game::new(&def);
+    // debajo de la salida (6, 1), mirando hacia ella
+    game.cam = Camera::new(vec2(6.5, 2.5), vec2(0.0, -1.0));
+    let forward = InputFrame { forward: true, ..Default::default() };
+    game.run_for(1.0, &forward);
+    assert!((game.cam.pos.y - 2.3).abs() < 1e-4, "{:?}", game.cam.pos);
+    assert!(!game.level.won);
+
+    game.level.coins = 0;
+    let events = game.run_for(1.0, &forward);
+    assert_eq!(events, [GameEvent::Won]);
+    assert!(LevelDef::parse(&format!("radio_jugador = 0.5\n{}", source())).is_err());
+}
+
+#[test]
+fn same_inputs_give_the_same_run() {
+    let def = level("", &[]);
+    let inputs: Vec<InputFrame> = (0..240)
+        .map(|i| InputFrame { forward: i % 3 != 0, left: i % 7 == 0, turn: if i % 40 < 5 { 0.05 } else { 0.0 }, ..Default::default() })
+        .collect();
+    let run = || {
+        let mut game = Game::new(&def);
+        for input in &inputs {
+            update(&mut game, input, TICK_DT);
+        }
+        (game.cam, game.level.elapsed, game.level.coins)
+    };
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn animation_time_follows_the_game_clock() {
+    let def = level("", &[]);
+    let mut game = Game::new(&def);
+    assert_eq!(game.interpolated_time(0.0), 0.0);
+    game.run_for(1.0, &InputFrame::default());
+    // entre el paso anterior y el actual, como la cámara
+    assert!((game.interpolated_time(1.0) - game.level.elapsed).abs() < 1e-6);
+    assert!((game.interpolated_time(0.5) - (game.level.elapsed - TICK_DT / 2.0)).abs() < 1e-6);
+}
+
+#[test]
+fn standing_on_a_hazard_hurts_once_per_invulnerable_spell_until_lost() {
+    let def = hazard_level(40);
+    let mut game = Game::new(&def);
+    let forward = InputFrame { forward: true, ..Default::default() };
+    let mut ticks = 0;
+    let first = loop {
+        let events = game.step(&forward);
+        ticks += 1;
+        if !events.is_empty() {
+            break events;
+        }
+        assert!(ticks < 60, "no llegó al peligro");
+    };
+    // el golpe llega en el paso que pisa la celda
+    assert_eq!(first, [GameEvent::HazardHit(1)]);
+    assert_eq!(ticks, (1.5 / (MOVE_SPEED * TICK_DT)).ceil() as u32);
+    assert_eq!(game.level.health, 60);
+
+    // quieto sobre el peligro: otro golpe al terminar cada invulnerabilidad, y la vida no baja de 0
+    let mut hits = Vec::new();
+    for tick in 1..=200 {
+        let events = game.step(&InputFrame::default());
+        if !events.is_empty() {
+            hits.push((tick, events, game.level.health));
+        }
+    }
+    let events: Vec<_> = hits.iter().map(|(_, e, health)| (e.as_slice(), *health)).collect();
+    assert_eq!(events, [(&[GameEvent::HazardHit(1)][..], 20), (&[GameEvent::HazardHit(1), GameEvent::Lost][..], 0)]);
+    // un paso de tolerancia: restar TICK_DT en f32 puede dejar un resto mínimo de invulnerabilidad
+    let spell = (INVULNERABLE_SECS / TICK_DT).round() as u32;
+    for gap in [hits[0].0, hits[1].0 - hits[0].0] {
+        assert!((spell..=spell + 1).contains(&gap), "{} pasos entre golpes", gap);
+    }
+    assert!(game.finished());
+    assert_eq!(game.level.damage_taken, 100);
+}